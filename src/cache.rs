@@ -0,0 +1,179 @@
+//! This module implements the on-disk cache of the synced todoist state.
+//!
+//! The cache lives in the users data directory and lets us render
+//! listings right away, before the server has been asked for changes.
+
+use dirs;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use config::Configuration;
+use state::State;
+
+// The version of the layout of the cache file, bump this whenever
+// the cached types change in an incompatible way.
+static CACHE_SCHEMA_VERSION: u32 = 8;
+
+// The directory, relative to the users data dir, holding our files.
+static CACHE_DIR: &str = "todr";
+
+// The name of the cache file.
+static CACHE_FILE: &str = "cache.json";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Cache {
+    /// The layout version this cache was written with.
+    schema_version: u32,
+
+    /// The server the state was synced from.
+    api_base_url: String,
+
+    /// A fingerprint of the token the state was synced with.
+    account: u64,
+
+    /// The cached state.
+    state: State,
+}
+
+fn cache_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(CACHE_DIR).join(CACHE_FILE))
+}
+
+/// The server and account the cache is written for.
+///
+/// The account is only known by its token before the first request, so a
+/// hash of the token stands in for it. The token itself is never written.
+fn owner() -> (String, u64) {
+    let mut hasher = DefaultHasher::new();
+    Configuration::api_token()
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    (Configuration::api_base_url(), hasher.finish())
+}
+
+fn parse(contents: &str, api_base_url: &str, account: u64) -> Option<State> {
+    let value: serde_json::Value = serde_json::from_str(contents).ok()?;
+    let schema_version = value.get("schema_version")?.as_u64()?;
+
+    // Caches written with another layout are invalidated,
+    // the following full sync fills them in again.
+    if schema_version != u64::from(CACHE_SCHEMA_VERSION) {
+        return None;
+    }
+
+    let cache: Cache = serde_json::from_value(value).ok()?;
+
+    // The sync token of another server or account is meaningless.
+    if cache.api_base_url != api_base_url || cache.account != account {
+        return None;
+    }
+
+    Some(cache.state)
+}
+
+/// Load the cached state, if there is a usable one.
+pub fn load() -> Option<State> {
+    let contents = fs::read_to_string(cache_path()?).ok()?;
+    let (api_base_url, account) = owner();
+    parse(&contents, &api_base_url, account)
+}
+
+/// Write a file only the user can read, as the cache holds their tasks.
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+
+    // Caches written by older versions may have been readable by others.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+
+    file.write_all(contents.as_bytes())
+}
+
+/// Write the state to the cache.
+///
+/// The cache is only an optimization, so failing
+/// to write it is reported but otherwise ignored.
+pub fn save(state: &State) {
+    let Some(path) = cache_path() else {
+        return;
+    };
+
+    let (api_base_url, account) = owner();
+    let cache = json!({
+        "schema_version": CACHE_SCHEMA_VERSION,
+        "api_base_url": api_base_url,
+        "account": account,
+        "state": state,
+    });
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| write_private(&path, &cache.to_string()));
+
+    if let Err(e) = result {
+        eprintln!("Failed to write cache {}: {}", path.display(), e);
+    }
+}
+
+#[test]
+fn parse_current_schema_test() {
+    let contents = format!(
+        r#"{{ "schema_version": {CACHE_SCHEMA_VERSION},
+            "api_base_url": "https://api.todoist.com", "account": 42, "state": {{
+            "sync_token": "abc", "items": [], "projects": [],
+            "sections": [], "labels": [], "filters": [], "notes": [],
+            "project_notes": [], "reminders": [], "collaborators": [],
+            "user": null
+        }} }}"#
+    );
+
+    let state = parse(&contents, "https://api.todoist.com", 42).unwrap();
+    assert_eq!(state.sync_token, "abc");
+
+    // Caches of another server or account are invalidated.
+    assert!(parse(&contents, "http://localhost:8080", 42).is_none());
+    assert!(parse(&contents, "https://api.todoist.com", 7).is_none());
+}
+
+#[cfg(test)]
+use state::test_user;
+
+#[test]
+fn token_not_cached_test() {
+    // The sync response carries the token, the state drops it.
+    let mut state = State::new();
+    state.user = Some(test_user("1"));
+
+    let contents = json!({
+        "schema_version": CACHE_SCHEMA_VERSION,
+        "api_base_url": "https://api.todoist.com",
+        "account": 42,
+        "state": state,
+    })
+    .to_string();
+    assert!(!contents.contains("0123456789abcdef"));
+    assert!(parse(&contents, "https://api.todoist.com", 42)
+        .unwrap()
+        .user
+        .is_some());
+}
+
+#[test]
+fn parse_unknown_schema_test() {
+    // Caches of other layouts are invalidated.
+    let contents = r#"{ "schema_version": 1, "state": {} }"#;
+    assert!(parse(contents, "https://api.todoist.com", 42).is_none());
+
+    // As are caches which are corrupt.
+    assert!(parse("{", "https://api.todoist.com", 42).is_none());
+}
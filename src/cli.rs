@@ -0,0 +1,112 @@
+//! This module implements the non-interactive todr command line.
+//!
+//! Each invocation runs a single command through the same handlers
+//! the REPL uses, and reports the outcome through the exit code.
+
+use cache;
+use error::TodrError;
+use handlers;
+use output::OutputFormat;
+use state::State;
+
+// The command succeeded.
+static EXIT_SUCCESS: i32 = 0;
+
+// The command failed, for example because the server couldn't be reached.
+static EXIT_FAILURE: i32 = 1;
+
+// The command line, or the arguments of the command, couldn't be understood.
+static EXIT_USAGE: i32 = 2;
+
+/// Run the command given on the command line, returning the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let Some((name, rest)) = args.split_first() else {
+        return EXIT_USAGE;
+    };
+
+    let Some(command) = handlers::Command::parse(name) else {
+        handlers::unknown_command(name);
+        return EXIT_USAGE;
+    };
+
+    let mut state = cache::load().unwrap_or_else(State::new);
+
+    match execute(&mut state, command, &command_line(command, rest)) {
+        Ok(()) => EXIT_SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            match e {
+                TodrError::Usage(_) => EXIT_USAGE,
+                _ => EXIT_FAILURE,
+            }
+        }
+    }
+}
+
+/// Join the arguments into the line the REPL would have been given.
+///
+/// The shell has already split the arguments, so the first one is
+/// quoted for the commands which split it off again.
+fn command_line(command: handlers::Command, args: &[String]) -> String {
+    match args.split_first() {
+        Some((target, rest)) if command.has_target() && target.contains(char::is_whitespace) => {
+            let mut words = vec![format!("\"{target}\"")];
+            words.extend(rest.iter().cloned());
+            words.join(" ")
+        }
+        _ => args.join(" "),
+    }
+}
+
+fn execute(state: &mut State, command: handlers::Command, args: &str) -> Result<(), TodrError> {
+    // Unlike in the REPL there is no later chance to refresh the
+    // cache, so make sure scripts always see up to date listings.
+    if command.is_listing() || command == handlers::Command::Export {
+        handlers::sync(state)?;
+    }
+
+    // Listings are printed as text, unless asked otherwise with `--format`.
+    handlers::execute_command(state, command, args, OutputFormat::Text)
+}
+
+#[test]
+fn command_line_test() {
+    let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| String::from(*a)).collect() };
+
+    assert_eq!(
+        command_line(
+            handlers::Command::Comment,
+            &args(&["buy milk", "Get oat milk"])
+        ),
+        "\"buy milk\" Get oat milk"
+    );
+    assert_eq!(
+        command_line(handlers::Command::Remind, &args(&["team standup", "15m"])),
+        "\"team standup\" 15m"
+    );
+    assert_eq!(
+        command_line(
+            handlers::Command::Comment,
+            &args(&["milk", "Get", "oat milk"])
+        ),
+        "milk Get oat milk"
+    );
+
+    // Commands taking the whole line get it as it is.
+    assert_eq!(
+        command_line(handlers::Command::Add, &args(&["Buy milk"])),
+        "Buy milk"
+    );
+}
+
+#[test]
+fn run_usage_test() {
+    let args = |args: &[&str]| -> Vec<String> { args.iter().map(|a| String::from(*a)).collect() };
+
+    assert_eq!(run(&args(&[])), EXIT_USAGE);
+    assert_eq!(run(&args(&["frobnicate"])), EXIT_USAGE);
+
+    // Commands missing their arguments fail before reaching the server.
+    assert_eq!(run(&args(&["comment"])), EXIT_USAGE);
+    assert_eq!(run(&args(&["remind", "milk"])), EXIT_USAGE);
+}
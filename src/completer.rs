@@ -8,7 +8,31 @@ pub struct CustomCompletion {
 
 impl CustomCompletion {
     pub fn new() -> Self {
-        let commands: Vec<&str> = vec!["help", "items", "projs", "quit"];
+        let commands: Vec<&str> = vec![
+            "add",
+            "comment",
+            "done",
+            "export",
+            "filter",
+            "filters",
+            "format",
+            "help",
+            "import",
+            "items",
+            "labels",
+            "notes",
+            "overdue",
+            "projs",
+            "quit",
+            "remind",
+            "reminders",
+            "rm",
+            "sync",
+            "today",
+            "undone",
+            "unremind",
+            "upcoming",
+        ];
 
         Self {
             commands,
@@ -74,8 +98,9 @@ fn verify_completion(input: &str, expected_completion: &str) {
 
 #[test]
 fn completion_test_items() {
-    // Verify that the completion for i completes to items.
-    verify_completion("i", "items");
+    // Verify that the completion for it completes to items,
+    // a lone i is ambiguous with import.
+    verify_completion("it", "items");
     verify_completion("ite", "items");
 }
 
@@ -1,11 +1,385 @@
 //! This module implements the todr configuration logic.
+//!
+//! Settings are read from `todr/config.toml` in the users configuration
+//! directory, and each of them can be overridden by an environment variable.
+use dirs;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
 use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use toml;
 
-pub enum Configuration {}
+// The directory, relative to the users config dir, holding our files.
+static CONFIG_DIR: &str = "todr";
+
+// The name of the configuration file.
+static CONFIG_FILE: &str = "config.toml";
+
+// The location of the todoist API, the endpoint paths are appended to it.
+static DEFAULT_API_BASE_URL: &str = "https://api.todoist.com";
+
+// The glyph marking items by their priority.
+static DEFAULT_PRIORITY_MARKER: &str = "!";
+
+// The configuration loaded at startup.
+static CONFIGURATION: OnceLock<Configuration> = OnceLock::new();
+
+/// Failures to load or write the configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file couldn't be read or written.
+    Io(PathBuf, io::Error),
+
+    /// The configuration file isn't valid TOML, or has unexpected values.
+    Invalid(PathBuf, String),
+
+    /// No API token was given during setup.
+    MissingToken,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "Failed to access {}: {}", path.display(), e),
+            Self::Invalid(path, e) => {
+                write!(f, "Invalid configuration in {}: {}", path.display(), e)
+            }
+            Self::MissingToken => write!(
+                f,
+                "An API token is required to use todr, set api_token or TODR_AUTHTOKEN"
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Configuration {
+    /// The todoist authentication token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_token: Option<String>,
+
+    /// The name of the project new items are added to, the inbox if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_project: Option<String>,
+
+    /// Whether output should be colored, defaults to true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<bool>,
+
+    /// The file the REPL history is kept in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_file: Option<String>,
+
+    /// The base URL of the todoist API, to point todr at a proxy or mock server.
+    /// Older configuration files call it `api_url`.
+    #[serde(alias = "api_url", skip_serializing_if = "Option::is_none")]
+    pub api_base_url: Option<String>,
+
+    /// The glyph shown in front of items with a priority, defaults to `!`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority_marker: Option<String>,
+
+    /// Whether trees are drawn with ASCII instead of box-drawing characters,
+    /// defaults to true unless the locale uses UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ascii_tree: Option<bool>,
+}
 
 impl Configuration {
+    /// Load the configuration, running the first-run setup if needed.
+    pub fn init() -> Result<(), ConfigError> {
+        let file = Self::read();
+
+        let mut configuration = file.as_ref().map_or_else(|_| Self::default(), Clone::clone);
+        configuration.apply_environment();
+
+        if configuration.api_token.is_none() {
+            // Files which can't be read are reported rather than replaced by
+            // the setup, and scripts fail instead of waiting for input.
+            let file = file?;
+            if !io::stdin().is_terminal() {
+                return Err(ConfigError::MissingToken);
+            }
+
+            configuration = Self::setup(file)?;
+        } else if let Err(e) = file {
+            eprintln!("{e}");
+        }
+
+        configuration.validate()?;
+
+        let _ = CONFIGURATION.set(configuration);
+        Ok(())
+    }
+
+    fn get() -> &'static Self {
+        CONFIGURATION.get_or_init(|| {
+            let mut configuration = Self::read().unwrap_or_default();
+            configuration.apply_environment();
+            configuration
+        })
+    }
+
     /// Obtain Todoist authentication token.
-    pub fn api_token() -> String {
-        env::var("TODR_AUTHTOKEN").expect("TODR_AUTHTOKEN is not set")
+    pub fn api_token() -> Result<String, ConfigError> {
+        Self::get()
+            .api_token
+            .clone()
+            .ok_or(ConfigError::MissingToken)
+    }
+
+    /// Obtain the base URL of the todoist API.
+    pub fn api_base_url() -> String {
+        Self::get()
+            .api_base_url
+            .clone()
+            .unwrap_or_else(|| String::from(DEFAULT_API_BASE_URL))
+    }
+
+    /// Obtain the name of the project new items are added to.
+    pub fn default_project() -> Option<String> {
+        Self::get().default_project.clone()
     }
+
+    /// Whether output should be colored.
+    pub fn color() -> bool {
+        Self::get().color.unwrap_or(true)
+    }
+
+    /// Obtain the configured REPL history file.
+    pub fn history_file() -> Option<String> {
+        Self::get().history_file.clone()
+    }
+
+    /// Obtain the glyph marking items by their priority.
+    pub fn priority_marker() -> String {
+        Self::get()
+            .priority_marker
+            .clone()
+            .unwrap_or_else(|| String::from(DEFAULT_PRIORITY_MARKER))
+    }
+
+    /// Whether trees should be drawn with ASCII characters only.
+    pub fn ascii_tree() -> bool {
+        Self::get().ascii_tree.unwrap_or_else(|| !locale_is_utf8())
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(CONFIG_DIR).join(CONFIG_FILE))
+    }
+
+    fn parse(path: &Path, contents: &str) -> Result<Self, ConfigError> {
+        toml::from_str(contents)
+            .map_err(|e| ConfigError::Invalid(path.to_path_buf(), e.to_string()))
+    }
+
+    /// Read the configuration file, without the environment applied.
+    fn read() -> Result<Self, ConfigError> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&path, &contents),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ConfigError::Io(path, e)),
+        }
+    }
+
+    /// Check the values which can't be checked by deserializing alone.
+    fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(ref base_url) = self.api_base_url {
+            let valid = Url::parse(base_url)
+                .is_ok_and(|url| url.scheme() == "http" || url.scheme() == "https");
+
+            if !valid {
+                let path = Self::path().unwrap_or_else(|| PathBuf::from(CONFIG_FILE));
+                let message = format!("api_base_url is not a http(s) URL: {base_url}");
+                return Err(ConfigError::Invalid(path, message));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Let environment variables override the values from the file.
+    fn apply_environment(&mut self) {
+        if let Ok(token) = env::var("TODR_AUTHTOKEN") {
+            self.api_token = Some(token);
+        }
+
+        if let Ok(project) = env::var("TODR_DEFAULT_PROJECT") {
+            self.default_project = Some(project);
+        }
+
+        if let Ok(color) = env::var("TODR_COLOR") {
+            self.color = Some(is_enabled(&color));
+        }
+
+        if let Ok(history_file) = env::var("TODR_HISTORY_FILE") {
+            self.history_file = Some(history_file);
+        }
+
+        if let Ok(base_url) = env::var("TODR_API_BASE_URL") {
+            self.api_base_url = Some(base_url);
+        }
+
+        if let Ok(marker) = env::var("TODR_PRIORITY_MARKER") {
+            self.priority_marker = Some(marker);
+        }
+
+        if let Ok(ascii_tree) = env::var("TODR_ASCII_TREE") {
+            self.ascii_tree = Some(is_enabled(&ascii_tree));
+        }
+    }
+
+    /// Write the configuration file, keeping a backup of any previous one.
+    fn save(&self) -> Result<PathBuf, ConfigError> {
+        let Some(path) = Self::path() else {
+            return Err(ConfigError::Io(
+                PathBuf::from(CONFIG_FILE),
+                io::Error::new(io::ErrorKind::NotFound, "No configuration directory"),
+            ));
+        };
+
+        let contents =
+            toml::to_string(self).map_err(|e| ConfigError::Invalid(path.clone(), e.to_string()))?;
+
+        let write = || -> io::Result<()> {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+
+            let backup = path.with_extension("toml.bak");
+            if path.exists() {
+                fs::rename(&path, &backup)?;
+            }
+
+            let mut file = fs::File::create(&path)?;
+            // The file holds the API token, so only the user may read it.
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(fs::Permissions::from_mode(0o600))?;
+                if backup.exists() {
+                    fs::set_permissions(&backup, fs::Permissions::from_mode(0o600))?;
+                }
+            }
+            file.write_all(contents.as_bytes())
+        };
+
+        write().map_err(|e| ConfigError::Io(path.clone(), e))?;
+        Ok(path)
+    }
+
+    /// Ask the user for their API token and add it to the configuration file.
+    fn setup(mut configuration: Self) -> Result<Self, ConfigError> {
+        println!("Welcome to todr! Let's set up your configuration.");
+        println!();
+        println!("Your API token can be found in the todoist web app,");
+        println!("under Settings > Integrations > API token.");
+        println!();
+        print!("API token: ");
+        let _ = io::stdout().flush();
+
+        let mut token = String::new();
+        io::stdin()
+            .read_line(&mut token)
+            .map_err(|e| ConfigError::Io(PathBuf::from("stdin"), e))?;
+
+        let token = token.trim();
+        if token.is_empty() {
+            println!();
+            return Err(ConfigError::MissingToken);
+        }
+
+        // The other settings of the file are kept as they are.
+        configuration.api_token = Some(String::from(token));
+
+        let path = configuration.save()?;
+        println!("Saved configuration to {}", path.display());
+
+        configuration.apply_environment();
+        Ok(configuration)
+    }
+}
+
+/// Whether the value of a boolean environment variable enables the setting.
+fn is_enabled(value: &str) -> bool {
+    !matches!(value, "0" | "false" | "no" | "off")
+}
+
+/// Whether the terminal is expected to render UTF-8, going by the locale.
+///
+/// The first of these variables which is set decides, as with `setlocale`.
+fn locale_is_utf8() -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+        .is_some_and(|locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+#[test]
+fn parse_config_test() {
+    let path = Path::new(CONFIG_FILE);
+    let contents = r#"
+        api_token = "0123456789abcdef"
+        default_project = "Work"
+        color = false
+    "#;
+
+    let configuration = Configuration::parse(path, contents).unwrap();
+    assert_eq!(configuration.api_token.unwrap(), "0123456789abcdef");
+    assert_eq!(configuration.default_project.unwrap(), "Work");
+    assert_eq!(configuration.color, Some(false));
+    assert_eq!(configuration.history_file, None);
+}
+
+#[test]
+fn parse_api_url_alias_test() {
+    let path = Path::new(CONFIG_FILE);
+
+    let configuration = Configuration::parse(path, "api_url = \"http://localhost:8080\"").unwrap();
+    assert_eq!(configuration.api_base_url.unwrap(), "http://localhost:8080");
+
+    // It is written back under its current name.
+    let configuration = Configuration {
+        api_base_url: Some(String::from("http://localhost:8080")),
+        ..Configuration::default()
+    };
+    let contents = toml::to_string(&configuration).unwrap();
+    assert!(contents.starts_with("api_base_url = "));
+}
+
+#[test]
+fn parse_invalid_config_test() {
+    let path = Path::new(CONFIG_FILE);
+
+    // Typos in the setting names are reported instead of ignored.
+    assert!(Configuration::parse(path, "api_tokn = \"abc\"").is_err());
+    assert!(Configuration::parse(path, "color = ").is_err());
+}
+
+#[test]
+fn validate_api_base_url_test() {
+    let mut configuration = Configuration {
+        api_base_url: Some(String::from("http://localhost:8080")),
+        ..Configuration::default()
+    };
+    assert!(configuration.validate().is_ok());
+
+    configuration.api_base_url = Some(String::from("localhost:8080"));
+    assert!(configuration.validate().is_err());
+
+    configuration.api_base_url = Some(String::from("not a url"));
+    assert!(configuration.validate().is_err());
 }
@@ -0,0 +1,306 @@
+//! This module implements parsing of the dates the todoist API hands us.
+//!
+//! Due dates come in three flavours: all-day dates (`2016-12-01`), floating
+//! dates with a time (`2016-12-01T12:00:00`), and dates fixed to a timezone
+//! which are given in UTC (`2016-12-01T12:00:00Z`). All of them are mapped
+//! to the wall clock of the user, as configured in their todoist settings.
+
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use std::cmp::Ordering;
+
+use types;
+
+/// A parsed due date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Due {
+    /// Due some time during the day.
+    AllDay(NaiveDate),
+
+    /// Due at a time, on the wall clock of the user.
+    At(NaiveDateTime),
+}
+
+impl Due {
+    /// The day the item is due on.
+    pub const fn date(self) -> NaiveDate {
+        match self {
+            Self::AllDay(date) => date,
+            Self::At(at) => at.date(),
+        }
+    }
+
+    /// The moment the item becomes overdue.
+    ///
+    /// Like the `xx:xx:59` due times of the old API, all-day items
+    /// are due at the very end of their day, so they sort after the
+    /// items with a time on the same day.
+    pub fn deadline(self) -> NaiveDateTime {
+        match self {
+            Self::AllDay(date) => date.and_time(end_of_day()),
+            Self::At(at) => at,
+        }
+    }
+}
+
+impl Ord for Due {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.deadline().cmp(&other.deadline())
+    }
+}
+
+impl PartialOrd for Due {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 59).unwrap_or_default()
+}
+
+/// The current time of the user, which dates are parsed and shown relative to.
+#[derive(Debug, Clone, Copy)]
+pub struct Clock {
+    /// The wall clock time of the user.
+    now: NaiveDateTime,
+
+    /// The offset of the users timezone from UTC.
+    offset: FixedOffset,
+}
+
+impl Clock {
+    pub const fn new(now: NaiveDateTime, offset: FixedOffset) -> Self {
+        Self { now, offset }
+    }
+
+    /// The clock of the user, in the timezone from their todoist settings,
+    /// or the timezone of this machine before the user has been synced.
+    pub fn for_user(user: Option<&types::UserStruct>) -> Self {
+        let offset = user
+            .and_then(|u| {
+                let minutes = i32::from(u.tz_info.hours) * 60 + i32::from(u.tz_info.minutes);
+                FixedOffset::east_opt(minutes * 60)
+            })
+            .unwrap_or_else(|| *Local::now().offset());
+
+        Self::new(Utc::now().with_timezone(&offset).naive_local(), offset)
+    }
+
+    pub const fn now(&self) -> NaiveDateTime {
+        self.now
+    }
+
+    pub const fn today(&self) -> NaiveDate {
+        self.now.date()
+    }
+
+    /// Parse the due date of an item.
+    pub fn due(&self, item: &types::ItemStruct) -> Option<Due> {
+        self.parse_due(&item.due.as_ref()?.date)
+    }
+
+    /// When a reminder goes off, for the time-based reminders.
+    pub fn reminder_time(
+        &self,
+        reminder: &types::ReminderStruct,
+        item: &types::ItemStruct,
+    ) -> Option<NaiveDateTime> {
+        match reminder.reminder_type {
+            types::ReminderType::Absolute => match self.parse_due(&reminder.due.as_ref()?.date)? {
+                Due::At(at) => Some(at),
+                Due::AllDay(date) => Some(date.and_time(NaiveTime::MIN)),
+            },
+
+            // Relative reminders can only be set on items due at a time.
+            types::ReminderType::Relative => match self.due(item)? {
+                Due::At(at) => Some(at - Duration::minutes(reminder.minute_offset?)),
+                Due::AllDay(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn parse_due(&self, date: &str) -> Option<Due> {
+        if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            return Some(Due::AllDay(date));
+        }
+
+        if let Ok(at) = NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S") {
+            return Some(Due::At(at));
+        }
+
+        self.timestamp(date).map(Due::At)
+    }
+
+    /// Parse a timestamp, such as the time an item was added at.
+    pub fn timestamp(&self, timestamp: &str) -> Option<NaiveDateTime> {
+        // The v9 API uses RFC 3339, the older APIs `Sun 23 Jun 2019 06:59:59 +0000`.
+        DateTime::parse_from_rfc3339(timestamp)
+            .or_else(|_| DateTime::parse_from_str(timestamp, "%a %d %b %Y %H:%M:%S %z"))
+            .ok()
+            .map(|at| at.with_timezone(&self.offset).naive_local())
+    }
+
+    /// Format a time on the users clock in RFC 3339, with the users offset.
+    pub fn rfc3339(&self, at: NaiveDateTime) -> Option<String> {
+        self.offset
+            .from_local_datetime(&at)
+            .single()
+            .map(|at| at.to_rfc3339())
+    }
+
+    /// Convert a time on the users clock to UTC.
+    pub fn utc(&self, at: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.offset
+            .from_local_datetime(&at)
+            .single()
+            .map(|at| at.naive_utc())
+    }
+
+    /// Whether the due date has passed.
+    pub fn is_overdue(&self, due: Due) -> bool {
+        due.deadline() < self.now
+    }
+
+    /// Describe a due date relative to now, such as `overdue 2d`,
+    /// `today 14:00` or `in 3 days`.
+    pub fn relative(&self, due: Due) -> String {
+        let days = (due.date() - self.today()).num_days();
+
+        let day = match days {
+            _ if days < 0 => return format!("overdue {}d", -days),
+            0 => String::from("today"),
+            1 => String::from("tomorrow"),
+            2..=6 => return format!("in {days} days"),
+            _ if due.date() - self.today() < Duration::days(365) => {
+                due.date().format("%b %-d").to_string()
+            }
+            _ => due.date().format("%b %-d %Y").to_string(),
+        };
+
+        match due {
+            Due::AllDay(_) => day,
+            Due::At(at) => format!("{day} {}", at.format("%H:%M")),
+        }
+    }
+}
+
+#[cfg(test)]
+fn test_clock() -> Clock {
+    // Sunday the 10th of March 2024 at 12:30, in UTC+1.
+    let now = NaiveDate::from_ymd_opt(2024, 3, 10)
+        .and_then(|d| d.and_hms_opt(12, 30, 0))
+        .unwrap();
+    Clock::new(now, FixedOffset::east_opt(3600).unwrap())
+}
+
+#[test]
+fn parse_due_test() {
+    let clock = test_clock();
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+    assert_eq!(
+        clock.parse_due("2016-12-01"),
+        Some(Due::AllDay(date(2016, 12, 1)))
+    );
+    assert_eq!(
+        clock.parse_due("2016-12-01T12:00:00"),
+        Some(Due::At(date(2016, 12, 1).and_hms_opt(12, 0, 0).unwrap()))
+    );
+
+    // Fixed dates are given in UTC and shown on the users clock.
+    assert_eq!(
+        clock.parse_due("2016-12-01T23:30:00Z"),
+        Some(Due::At(date(2016, 12, 2).and_hms_opt(0, 30, 0).unwrap()))
+    );
+
+    assert_eq!(clock.parse_due("tomorrow"), None);
+}
+
+#[test]
+fn parse_timestamp_test() {
+    let clock = test_clock();
+    let expected = NaiveDate::from_ymd_opt(2019, 6, 23)
+        .and_then(|d| d.and_hms_opt(7, 59, 59))
+        .unwrap();
+
+    assert_eq!(
+        clock.timestamp("2019-06-23T06:59:59.000000Z"),
+        Some(expected)
+    );
+    assert_eq!(
+        clock.timestamp("Sun 23 Jun 2019 06:59:59 +0000"),
+        Some(expected)
+    );
+}
+
+#[test]
+fn relative_due_test() {
+    let clock = test_clock();
+    let due = |date: &str| clock.parse_due(date).unwrap();
+
+    assert_eq!(clock.relative(due("2024-03-08")), "overdue 2d");
+    assert_eq!(clock.relative(due("2024-03-10")), "today");
+    assert_eq!(clock.relative(due("2024-03-10T14:00:00")), "today 14:00");
+    assert_eq!(clock.relative(due("2024-03-11T09:15:00")), "tomorrow 09:15");
+    assert_eq!(clock.relative(due("2024-03-13")), "in 3 days");
+    assert_eq!(clock.relative(due("2024-04-01")), "Apr 1");
+    assert_eq!(clock.relative(due("2025-04-01")), "Apr 1 2025");
+
+    // All-day items are only overdue once their day has passed.
+    assert!(!clock.is_overdue(due("2024-03-10")));
+    assert!(clock.is_overdue(due("2024-03-10T09:00:00")));
+    assert!(due("2024-03-10T09:00:00") < due("2024-03-10"));
+}
+
+#[cfg(test)]
+use serde_json;
+#[cfg(test)]
+use state::{test_due, test_item};
+
+#[test]
+fn reminder_time_test() {
+    let clock = test_clock();
+    let at = |h, m| {
+        NaiveDate::from_ymd_opt(2024, 3, 11)
+            .and_then(|d| d.and_hms_opt(h, m, 0))
+            .unwrap()
+    };
+
+    let item = |date: &str| {
+        let mut item = test_item("1", "Standup");
+        item.due = Some(test_due(date));
+        item
+    };
+    let reminder = |reminder_type: &str, due: Option<&str>| -> types::ReminderStruct {
+        serde_json::from_value(json!({
+            "id": "2", "notify_uid": "1", "item_id": "1", "type": reminder_type,
+            "due": due.map(test_due),
+            "minute_offset": 30, "is_deleted": false
+        }))
+        .unwrap()
+    };
+
+    // Relative reminders go off before the item is due, if it is due at a time.
+    let relative = reminder("relative", None);
+    assert_eq!(
+        clock.reminder_time(&relative, &item("2024-03-11T09:30:00")),
+        Some(at(9, 0))
+    );
+    assert_eq!(clock.reminder_time(&relative, &item("2024-03-11")), None);
+
+    // Absolute reminders are given in UTC, like fixed due dates.
+    let absolute = reminder("absolute", Some("2024-03-11T07:00:00Z"));
+    assert_eq!(
+        clock.reminder_time(&absolute, &item("2024-03-11")),
+        Some(at(8, 0))
+    );
+
+    let location = reminder("location", None);
+    assert_eq!(
+        clock.reminder_time(&location, &item("2024-03-11T09:30:00")),
+        None
+    );
+}
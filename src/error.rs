@@ -0,0 +1,118 @@
+//! This module implements the todr error type.
+//!
+//! Every failure a command can run into is mapped to a `TodrError`,
+//! so the REPL can report it and carry on with the next command.
+
+use reqwest;
+use serde_json;
+use std::error;
+use std::fmt;
+use std::io;
+use term;
+
+use config::ConfigError;
+use query::QueryError;
+
+#[derive(Debug)]
+pub enum TodrError {
+    /// The todoist server couldn't be reached.
+    Network(reqwest::Error),
+
+    /// The todoist server responded with an unexpected HTTP status.
+    HttpStatus(reqwest::StatusCode),
+
+    /// The todoist server rejected the API token.
+    Auth,
+
+    /// The response of the todoist server couldn't be understood.
+    Deserialize(String),
+
+    /// The configuration is missing or invalid.
+    Config(ConfigError),
+
+    /// Writing to the terminal failed.
+    Terminal(String),
+
+    /// A file given to a command couldn't be read or written.
+    File(String, io::Error),
+
+    /// The todoist server rejected a command.
+    Command(String),
+
+    /// The command was given invalid arguments.
+    Usage(String),
+}
+
+impl fmt::Display for TodrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Network(e) => write!(f, "Failed to reach the todoist server: {e}"),
+            Self::HttpStatus(status) => write!(f, "The todoist server responded with {status}"),
+            Self::Auth => write!(
+                f,
+                "The todoist server rejected the API token, please check your configuration"
+            ),
+            Self::Deserialize(e) => write!(f, "Failed to understand the todoist response: {e}"),
+            Self::Config(e) => write!(f, "{e}"),
+            Self::Terminal(e) => write!(f, "Failed to write to the terminal: {e}"),
+            Self::File(path, e) => write!(f, "Failed to access {path}: {e}"),
+            Self::Command(e) | Self::Usage(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl error::Error for TodrError {}
+
+impl From<reqwest::Error> for TodrError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_decode() {
+            Self::Deserialize(error.to_string())
+        } else {
+            Self::Network(error)
+        }
+    }
+}
+
+impl From<serde_json::Error> for TodrError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Deserialize(error.to_string())
+    }
+}
+
+impl From<ConfigError> for TodrError {
+    fn from(error: ConfigError) -> Self {
+        Self::Config(error)
+    }
+}
+
+impl From<term::Error> for TodrError {
+    fn from(error: term::Error) -> Self {
+        Self::Terminal(error.to_string())
+    }
+}
+
+impl From<QueryError> for TodrError {
+    fn from(error: QueryError) -> Self {
+        Self::Usage(error.to_string())
+    }
+}
+
+#[cfg(test)]
+use query::Query;
+
+#[test]
+fn error_conversion_test() {
+    let error = TodrError::from(serde_json::from_str::<u8>("{").unwrap_err());
+    assert!(matches!(error, TodrError::Deserialize(_)));
+    assert!(error
+        .to_string()
+        .starts_with("Failed to understand the todoist response: "));
+
+    // Invalid queries are the users mistake, not a failure of todr.
+    let error = TodrError::from(Query::parse("today &").unwrap_err());
+    assert!(matches!(error, TodrError::Usage(_)));
+    assert!(error.to_string().starts_with("Invalid query: "));
+
+    let error = TodrError::from(ConfigError::MissingToken);
+    assert_eq!(error.to_string(), ConfigError::MissingToken.to_string());
+}
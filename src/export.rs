@@ -0,0 +1,614 @@
+//! This module implements exporting the synced state to files.
+//!
+//! Each format is rendered to a string from the items or projects in
+//! the order they are listed in, and written out by the export command.
+
+use dates::{Clock, Due};
+use error::TodrError;
+use ical;
+use state::{hierarchy_order, State, TreePosition};
+use todotxt;
+use types;
+
+// The usage of the export command.
+static USAGE: &str = "Usage: export csv|md|org|ics|todotxt [options] <path>, \
+                      or export csv projects <path>";
+
+/// The file formats which can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma separated values, for spreadsheets.
+    Csv,
+
+    /// A Markdown outline with a checkbox for each item.
+    Markdown,
+
+    /// An Org-mode outline with a TODO heading for each item.
+    Org,
+
+    /// An iCalendar file with the items which have a due date.
+    Ics,
+
+    /// A todo.txt file with a line for each item.
+    TodoTxt,
+}
+
+/// The resources which can be exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exported {
+    Items,
+    Projects,
+}
+
+/// The arguments of the export command.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExportArgs {
+    /// The format to write.
+    pub format: ExportFormat,
+
+    /// What to write, items unless given.
+    pub exported: Exported,
+
+    /// Whether items due at a time are written as calendar events
+    /// instead of to-dos, only for iCalendar.
+    pub events: bool,
+
+    /// Whether completed items are written too, only for todo.txt.
+    pub completed: bool,
+
+    /// The file to write to.
+    pub path: String,
+}
+
+impl ExportArgs {
+    /// Parse `<format> [options] [items|projects] <path>`.
+    pub fn parse(args: &str) -> Result<Self, TodrError> {
+        let usage = || TodrError::Usage(String::from(USAGE));
+
+        let (format, rest) = args.split_once(' ').ok_or_else(usage)?;
+        let format = match format {
+            "csv" => ExportFormat::Csv,
+            "md" => ExportFormat::Markdown,
+            "org" => ExportFormat::Org,
+            "ics" => ExportFormat::Ics,
+            "todotxt" => ExportFormat::TodoTxt,
+            _ => return Err(usage()),
+        };
+
+        let mut events = false;
+        let mut completed = false;
+        let mut rest = rest.trim();
+        while let Some((option, tail)) = rest.split_once(' ').filter(|(o, _)| o.starts_with("--")) {
+            match (option, format) {
+                ("--events", ExportFormat::Ics) => events = true,
+                ("--completed", ExportFormat::TodoTxt) => completed = true,
+                _ => return Err(usage()),
+            }
+            rest = tail.trim();
+        }
+
+        let (exported, path) = match rest.split_once(' ') {
+            Some(("items", path)) => (Exported::Items, path.trim()),
+            Some(("projects", path)) => (Exported::Projects, path.trim()),
+            _ => (Exported::Items, rest),
+        };
+
+        // The outline formats already list the projects along with their items.
+        if path.is_empty() || (exported == Exported::Projects && format != ExportFormat::Csv) {
+            return Err(usage());
+        }
+
+        Ok(Self {
+            format,
+            exported,
+            events,
+            completed,
+            path: String::from(path),
+        })
+    }
+
+    /// Whether the item is part of the export.
+    ///
+    /// Completed items are exported along with their state, except
+    /// to todo.txt unless asked for, and calendars only hold the
+    /// items with a due date.
+    pub const fn includes(&self, item: &types::ItemStruct) -> bool {
+        match self.format {
+            ExportFormat::Ics => item.due.is_some(),
+            ExportFormat::TodoTxt => !item.checked || self.completed,
+            _ => true,
+        }
+    }
+}
+
+/// Render the items, ordered depth first, in the requested format.
+pub fn items(
+    export: &ExportArgs,
+    state: &State,
+    clock: &Clock,
+    items: &[(TreePosition, &types::ItemStruct)],
+) -> String {
+    match export.format {
+        ExportFormat::Csv => items_csv(state, clock, items),
+        ExportFormat::Markdown => items_markdown(state, clock, items),
+        ExportFormat::Org => items_org(state, clock, items),
+        ExportFormat::Ics => {
+            let items: Vec<&types::ItemStruct> = items.iter().map(|(_, item)| *item).collect();
+            ical::calendar(clock, &items, export.events)
+        }
+        ExportFormat::TodoTxt => items_todotxt(state, clock, items),
+    }
+}
+
+/// Quote a CSV field if needed, as described in RFC 4180.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+fn csv_record(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+    fields.join(",") + "\r\n"
+}
+
+fn items_csv(state: &State, clock: &Clock, items: &[(TreePosition, &types::ItemStruct)]) -> String {
+    let mut csv = csv_record(&[
+        "content",
+        "project",
+        "section",
+        "labels",
+        "priority",
+        "due",
+        "assignee",
+        "completed",
+    ]);
+
+    for (_, item) in items {
+        let project = state.project(&item.project_id).map(|p| p.name.as_str());
+        let section = item
+            .section_id
+            .as_ref()
+            .and_then(|id| state.section(id))
+            .map(|s| s.name.as_str());
+        let assignee = item
+            .responsible_uid
+            .as_ref()
+            .and_then(|uid| state.user_name(uid));
+
+        let due = match clock.due(item) {
+            Some(Due::AllDay(date)) => date.format("%Y-%m-%d").to_string(),
+            Some(Due::At(at)) => at.format("%Y-%m-%d %H:%M").to_string(),
+            None => String::new(),
+        };
+
+        csv.push_str(&csv_record(&[
+            &item.content,
+            project.unwrap_or_default(),
+            section.unwrap_or_default(),
+            &item.labels.join(", "),
+            &priority_name(item.priority),
+            &due,
+            assignee.unwrap_or_default(),
+            if item.checked { "true" } else { "false" },
+        ]));
+    }
+
+    csv
+}
+
+/// Render the projects, ordered depth first, as CSV.
+pub fn projects_csv(state: &State, projects: &[(TreePosition, &types::ProjectStruct)]) -> String {
+    let mut csv = csv_record(&[
+        "name", "parent", "color", "favorite", "shared", "archived", "items",
+    ]);
+
+    for (_, project) in projects {
+        let parent = project
+            .parent_id
+            .as_ref()
+            .and_then(|id| state.project(id))
+            .map(|p| p.name.as_str());
+        let items = state
+            .items
+            .iter()
+            .filter(|i| i.project_id == project.id && !i.checked)
+            .count();
+
+        csv.push_str(&csv_record(&[
+            &project.name,
+            parent.unwrap_or_default(),
+            &project.color,
+            &project.is_favorite.to_string(),
+            &project.shared.to_string(),
+            &project.is_archived.to_string(),
+            &items.to_string(),
+        ]));
+    }
+
+    csv
+}
+
+/// The heading of the items of archived projects, and of those
+/// in projects which aren't known locally.
+static OTHER_PROJECTS: &str = "Other projects";
+
+/// A project of an outline, with its items.
+struct OutlineSection<'a, 'b> {
+    /// The depth of the project below its top-level ancestor.
+    depth: usize,
+
+    /// The name of the project.
+    name: &'a str,
+
+    /// The items of the project, ordered depth first.
+    items: Vec<&'b (TreePosition, &'a types::ItemStruct)>,
+}
+
+/// Walk the projects depth first, pairing each of them with its items.
+fn outline<'a, 'b>(
+    state: &'a State,
+    items: &'b [(TreePosition, &'a types::ItemStruct)],
+) -> Vec<OutlineSection<'a, 'b>> {
+    let projects: Vec<&types::ProjectStruct> =
+        state.projects.iter().filter(|p| !p.is_archived).collect();
+
+    let mut sections: Vec<OutlineSection> = hierarchy_order(&projects)
+        .into_iter()
+        .map(|(position, project)| OutlineSection {
+            depth: position.last.len().saturating_sub(1),
+            name: &project.name,
+            items: items
+                .iter()
+                .filter(|(_, item)| item.project_id == project.id)
+                .collect(),
+        })
+        .collect();
+
+    // The items of projects without a section are kept at the end.
+    let others: Vec<&(TreePosition, &types::ItemStruct)> = items
+        .iter()
+        .filter(|(_, item)| !projects.iter().any(|p| p.id == item.project_id))
+        .collect();
+
+    if !others.is_empty() {
+        sections.push(OutlineSection {
+            depth: 0,
+            name: OTHER_PROJECTS,
+            items: others,
+        });
+    }
+
+    sections
+}
+
+/// The depth of an item below its top-level ancestor.
+const fn item_depth(position: &TreePosition) -> usize {
+    position.last.len().saturating_sub(1)
+}
+
+/// Keep multi-line content on the line of its item.
+fn single_line(content: &str) -> String {
+    content.lines().collect::<Vec<&str>>().join(" ")
+}
+
+fn items_markdown(
+    state: &State,
+    clock: &Clock,
+    items: &[(TreePosition, &types::ItemStruct)],
+) -> String {
+    let mut lines = Vec::new();
+
+    for section in outline(state, items) {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+
+        // Markdown has no headings below the sixth level.
+        let level = "#".repeat((section.depth + 1).min(6));
+        lines.push(format!("{level} {}", section.name));
+
+        if !section.items.is_empty() {
+            lines.push(String::new());
+        }
+
+        for (position, item) in section.items {
+            let mut words = vec![
+                format!("- [{}]", if item.checked { "x" } else { " " }),
+                single_line(&item.content),
+            ];
+
+            match clock.due(item) {
+                Some(Due::AllDay(date)) => words.push(format!("(due {date})")),
+                Some(Due::At(at)) => words.push(format!("(due {})", at.format("%Y-%m-%d %H:%M"))),
+                None => {}
+            }
+
+            words.extend(item.labels.iter().map(|label| markdown_label(label)));
+
+            let indent = "  ".repeat(item_depth(position));
+            lines.push(format!("{indent}{}", words.join(" ")));
+        }
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn items_org(state: &State, clock: &Clock, items: &[(TreePosition, &types::ItemStruct)]) -> String {
+    let mut lines = Vec::new();
+
+    for section in outline(state, items) {
+        let level = section.depth + 1;
+        lines.push(format!("{} {}", "*".repeat(level), section.name));
+
+        for (position, item) in section.items {
+            let stars = "*".repeat(level + 1 + item_depth(position));
+            let keyword = if item.checked { "DONE" } else { "TODO" };
+            let mut headline = vec![stars.clone(), String::from(keyword)];
+            headline.push(single_line(&item.content));
+
+            if !item.labels.is_empty() {
+                let tags: Vec<String> = item.labels.iter().map(|l| org_tag(l)).collect();
+                headline.push(format!(":{}:", tags.join(":")));
+            }
+            lines.push(headline.join(" "));
+
+            // Planning lines are indented to the text of their headline.
+            let indent = " ".repeat(stars.len() + 1);
+            let mut planning = Vec::new();
+
+            if item.checked {
+                let closed = item.completed_at.as_ref().and_then(|t| clock.timestamp(t));
+                if let Some(closed) = closed {
+                    planning.push(format!("CLOSED: [{}]", closed.format("%Y-%m-%d %a %H:%M")));
+                }
+            }
+
+            // Recurring items are planned rather than due for good,
+            // the way habits are kept in Org-mode.
+            if let Some(due) = clock.due(item) {
+                let keyword = match item.due {
+                    Some(ref due) if due.is_recurring => "SCHEDULED",
+                    _ => "DEADLINE",
+                };
+                let timestamp = match due {
+                    Due::AllDay(date) => date.format("%Y-%m-%d %a"),
+                    Due::At(at) => at.format("%Y-%m-%d %a %H:%M"),
+                };
+                planning.push(format!("{keyword}: <{timestamp}>"));
+            }
+
+            if !planning.is_empty() {
+                lines.push(format!("{indent}{}", planning.join(" ")));
+            }
+        }
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Labels which aren't a single plain word are written as code, so
+/// they stay one piece and their characters aren't taken as markup.
+fn markdown_label(label: &str) -> String {
+    if label
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        format!("@{label}")
+    } else {
+        format!("`@{}`", label.replace('`', "'"))
+    }
+}
+
+/// Org-mode tags may only contain letters, numbers, `_`, `@`, `#` and `%`.
+fn org_tag(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%') => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn items_todotxt(
+    state: &State,
+    clock: &Clock,
+    items: &[(TreePosition, &types::ItemStruct)],
+) -> String {
+    let date =
+        |timestamp: Option<&String>| timestamp.and_then(|t| clock.timestamp(t)).map(|t| t.date());
+
+    items
+        .iter()
+        .map(|(_, item)| {
+            let task = todotxt::Task {
+                completed: item.checked,
+                priority: todotxt::priority_from_api(item.priority),
+                completion_date: date(item.completed_at.as_ref()).filter(|_| item.checked),
+                creation_date: date(item.added_at.as_ref()),
+                description: single_line(&item.content),
+                projects: state
+                    .project(&item.project_id)
+                    .map(|p| p.name.clone())
+                    .into_iter()
+                    .collect(),
+                contexts: item.labels.clone(),
+                due: clock.due(item).map(Due::date),
+            };
+
+            task.to_string() + "\n"
+        })
+        .collect()
+}
+
+/// The name of an API priority as shown in todoist, `p1` being the most urgent.
+fn priority_name(priority: u8) -> String {
+    format!("p{}", 5 - priority.clamp(1, 4))
+}
+
+#[cfg(test)]
+use chrono::{FixedOffset, NaiveDate};
+
+#[cfg(test)]
+use serde_json;
+#[cfg(test)]
+use state::{test_due, test_item};
+
+#[cfg(test)]
+fn test_clock() -> Clock {
+    let now = NaiveDate::from_ymd_opt(2024, 3, 10)
+        .and_then(|d| d.and_hms_opt(12, 0, 0))
+        .unwrap();
+    Clock::new(now, FixedOffset::east_opt(0).unwrap())
+}
+
+#[cfg(test)]
+fn test_state() -> State {
+    let project = |id: &str, name: &str, parent_id: Option<&str>| {
+        json!({
+            "id": id, "name": name, "color": "blue", "parent_id": parent_id,
+            "child_order": 1, "collapsed": false, "shared": false,
+            "inbox_project": false, "is_favorite": false, "is_deleted": false,
+            "is_archived": false
+        })
+    };
+
+    let mut docs = test_item("1", "Update docs");
+    docs.project_id = String::from("20");
+    docs.due = Some(test_due("2024-03-13"));
+
+    let mut page = test_item("2", "Add testing page");
+    page.project_id = String::from("20");
+    page.parent_id = Some(String::from("1"));
+    page.due = Some(test_due("2024-03-11T09:30:00"));
+    page.due.as_mut().unwrap().is_recurring = true;
+    page.labels = vec![String::from("work"), String::from("deep work")];
+
+    let sync_state = json!({
+        "full_sync": true, "sync_token": "next",
+        "projects": [project("10", "Work", None), project("20", "Wiki", Some("10"))],
+        "items": [docs, page]
+    });
+
+    let mut state = State::new();
+    state.merge(serde_json::from_value(sync_state).unwrap());
+    state
+}
+
+#[cfg(test)]
+fn test_items(state: &State) -> Vec<(TreePosition, &types::ItemStruct)> {
+    let items: Vec<&types::ItemStruct> = state.items.iter().collect();
+    hierarchy_order(&items)
+}
+
+#[test]
+fn parse_export_args_test() {
+    let export = ExportArgs::parse("csv projects exports/todo list.csv").unwrap();
+    assert_eq!(export.format, ExportFormat::Csv);
+    assert_eq!(export.exported, Exported::Projects);
+    assert_eq!(export.path, "exports/todo list.csv");
+
+    let export = ExportArgs::parse("csv todo.csv").unwrap();
+    assert_eq!(export.exported, Exported::Items);
+    assert_eq!(export.path, "todo.csv");
+
+    let export = ExportArgs::parse("org notes/todo.org").unwrap();
+    assert_eq!(export.format, ExportFormat::Org);
+    assert!(ExportArgs::parse("md projects todo.md").is_err());
+
+    let export = ExportArgs::parse("ics --events todo.ics").unwrap();
+    assert!(export.events);
+    assert_eq!(export.path, "todo.ics");
+    assert!(ExportArgs::parse("csv --events todo.csv").is_err());
+
+    let export = ExportArgs::parse("todotxt --completed done.txt").unwrap();
+    assert_eq!(export.format, ExportFormat::TodoTxt);
+    assert!(export.completed);
+    assert_eq!(export.path, "done.txt");
+
+    assert!(ExportArgs::parse("csv").is_err());
+    assert!(ExportArgs::parse("xml todo.xml").is_err());
+}
+
+#[test]
+fn items_csv_test() {
+    let mut item = test_item("1", "Buy milk, eggs\nand \"good\" bread");
+    item.priority = 4;
+    item.due = Some(test_due("2024-03-09"));
+    item.labels = vec![String::from("food"), String::from("errands")];
+
+    let state = State::new();
+    let csv = items_csv(&state, &test_clock(), &[(TreePosition::default(), &item)]);
+
+    assert_eq!(
+        csv,
+        "content,project,section,labels,priority,due,assignee,completed\r\n\
+         \"Buy milk, eggs\nand \"\"good\"\" bread\",,,\"food, errands\",p1,2024-03-09,,false\r\n"
+    );
+}
+
+#[test]
+fn items_markdown_test() {
+    let state = test_state();
+    let markdown = items_markdown(&state, &test_clock(), &test_items(&state));
+
+    assert_eq!(
+        markdown,
+        "# Work\n\
+         \n\
+         ## Wiki\n\
+         \n\
+         - [ ] Update docs (due 2024-03-13)\n\
+         \x20 - [ ] Add testing page (due 2024-03-11 09:30) @work `@deep work`\n"
+    );
+}
+
+#[test]
+fn outline_other_projects_test() {
+    let state = test_state();
+    let mut item = test_item("3", "Plan trip");
+    item.project_id = String::from("99");
+
+    // Items of projects which aren't listed are kept under a heading of their own.
+    let mut items = test_items(&state);
+    items.push((TreePosition::default(), &item));
+
+    let markdown = items_markdown(&state, &test_clock(), &items);
+    assert!(markdown.ends_with("# Other projects\n\n- [ ] Plan trip\n"));
+
+    let org = items_org(&state, &test_clock(), &items);
+    assert!(org.ends_with("* Other projects\n** TODO Plan trip\n"));
+}
+
+#[test]
+fn items_org_test() {
+    let state = test_state();
+    let org = items_org(&state, &test_clock(), &test_items(&state));
+
+    assert_eq!(
+        org,
+        "* Work\n\
+         ** Wiki\n\
+         *** TODO Update docs\n\
+         \x20   DEADLINE: <2024-03-13 Wed>\n\
+         **** TODO Add testing page :work:deep_work:\n\
+         \x20    SCHEDULED: <2024-03-11 Mon 09:30>\n"
+    );
+}
+
+#[test]
+fn items_todotxt_test() {
+    let state = test_state();
+    let todotxt = items_todotxt(&state, &test_clock(), &test_items(&state));
+
+    assert_eq!(
+        todotxt,
+        "Update docs +Wiki due:2024-03-13\n\
+         Add testing page +Wiki @work @deep_work due:2024-03-11\n"
+    );
+}
@@ -1,17 +1,53 @@
 //! This module implements the todr command execution logic.
 
-use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fs;
+use std::sync::mpsc;
+use std::thread;
 
 use reqwest;
+use reqwest::StatusCode;
+use serde_json;
+use uuid::Uuid;
 
 // Use our internal types module.
+use cache;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
 use config::Configuration;
+use dates::{Clock, Due};
+use error::TodrError;
+use export;
+use export::{ExportArgs, Exported};
+use output;
+use output::{ItemOutput, OutputFormat, ProjectOutput, ReminderOutput};
+use query::{Context, Query};
 use renderer;
+use state::{hierarchy_order, State, TreePosition};
+use todotxt;
 use types;
 
-// Endpoint for REST communication with the Todoist.
-static TODOIST_API: &'static str = "https://todoist.com/API/v7/sync";
+// The path of the sync endpoint, relative to the API base URL.
+static SYNC_ENDPOINT: &str = "sync/v9/sync";
+
+// The path of the REST tasks endpoint, which can evaluate filter queries.
+static TASKS_ENDPOINT: &str = "rest/v2/tasks";
+
+// The endpoint listing the completed items.
+static COMPLETED_ENDPOINT: &str = "sync/v9/completed/get_all";
+
+// The most completed items the server sends per request.
+static COMPLETED_PAGE_SIZE: usize = 200;
+
+// The sync API accepts at most this many commands in a single request.
+static MAX_BATCH_COMMANDS: usize = 100;
+
+// The most days `upcoming` lists, one heading per day.
+static MAX_UPCOMING_DAYS: i64 = 365;
+
+/// Build the URL of an endpoint for the given API base URL.
+fn api_endpoint(base_url: &str, endpoint: &str) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), endpoint)
+}
 
 /// Used to specify what resources to fetch from the server.
 /// It should be a JSON-encoded array of strings.
@@ -19,6 +55,7 @@ static TODOIST_API: &'static str = "https://todoist.com/API/v7/sync";
 /// Here is a list of available resource types:
 /// -  labels,
 /// -  projects,
+/// -  sections,
 /// -  items,
 /// -  notes,
 /// -  filters,
@@ -32,107 +69,748 @@ static TODOIST_API: &'static str = "https://todoist.com/API/v7/sync";
 enum TodrResourceType {
     Items,
     Projects,
+    Sections,
+    Labels,
+    Filters,
+    Notes,
+    ProjectNotes,
+    Reminders,
+    Collaborators,
+    User,
 }
 
-fn to_resource_type(resource_type: &TodrResourceType) -> String {
-    let resource = match resource_type {
-        TodrResourceType::Items => String::from("items"),
-        TodrResourceType::Projects => String::from("projects"),
-    };
+/// The resources we keep in the local state.
+///
+/// They are always synced together, as the sync token the server hands
+/// back is only valid for the set of resources that was requested.
+static SYNCED_RESOURCES: [TodrResourceType; 10] = [
+    TodrResourceType::Items,
+    TodrResourceType::Projects,
+    TodrResourceType::Sections,
+    TodrResourceType::Labels,
+    TodrResourceType::Filters,
+    TodrResourceType::Notes,
+    TodrResourceType::ProjectNotes,
+    TodrResourceType::Reminders,
+    TodrResourceType::Collaborators,
+    TodrResourceType::User,
+];
+
+fn to_resource_types(resource_types: &[TodrResourceType]) -> String {
+    let resources: Vec<String> = resource_types
+        .iter()
+        .map(|resource_type| {
+            let resource = match resource_type {
+                TodrResourceType::Items => "items",
+                TodrResourceType::Projects => "projects",
+                TodrResourceType::Sections => "sections",
+                TodrResourceType::Labels => "labels",
+                TodrResourceType::Filters => "filters",
+                TodrResourceType::Notes => "notes",
+                TodrResourceType::ProjectNotes => "project_notes",
+                TodrResourceType::Reminders => "reminders",
+                TodrResourceType::Collaborators => "collaborators",
+                TodrResourceType::User => "user",
+            };
+
+            format!("\"{resource}\"")
+        })
+        .collect();
+
+    format!("[{}]", resources.join(","))
+}
+
+/// Post a request to the sync endpoint and decode its response.
+fn post_sync(params: &HashMap<&str, &str>) -> Result<types::SyncStruct, TodrError> {
+    // Fetch the token from the users configuration.
+    let auth_token = Configuration::api_token()?;
 
-    format!("[\"{}\"]", &resource)
+    let client = reqwest::blocking::Client::new();
+
+    // Issue the request.
+    let response = client
+        .post(api_endpoint(&Configuration::api_base_url(), SYNC_ENDPOINT))
+        .bearer_auth(auth_token)
+        .form(params)
+        .send()?;
+
+    common_response_handler(&response)?;
+
+    let body = response.text()?;
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// Ask the server for the ids of the items matching a filter query.
+fn fetch_filter_item_ids(query: &str) -> Result<Vec<String>, TodrError> {
+    let auth_token = Configuration::api_token()?;
+
+    let client = reqwest::blocking::Client::new();
+
+    // The sync API can't evaluate queries, so the REST API is used.
+    //
+    // Link: https://developer.todoist.com/rest/v2/#get-active-tasks
+    let response = client
+        .get(api_endpoint(&Configuration::api_base_url(), TASKS_ENDPOINT))
+        .bearer_auth(auth_token)
+        .query(&[("filter", query)])
+        .send()?;
+
+    common_response_handler(&response)?;
+
+    let body = response.text()?;
+    let tasks: Vec<types::TaskStruct> = serde_json::from_str(&body)?;
+    Ok(tasks.into_iter().map(|task| task.id).collect())
 }
 
-fn execute_request(
-    resource_type: &TodrResourceType,
-) -> Result<reqwest::blocking::Response, reqwest::Error> {
-    // Fetch the token from the users environment.
-    let auth_token = Configuration::api_token();
+/// Fetch all completed items, which syncs don't send.
+fn fetch_completed_items() -> Result<Vec<types::ItemStruct>, TodrError> {
+    let auth_token = Configuration::api_token()?;
+
+    let client = reqwest::blocking::Client::new();
+
+    // Link: https://developer.todoist.com/sync/v9/#get-all-completed-items
+    let limit = COMPLETED_PAGE_SIZE.to_string();
+    let mut offset = 0;
+    let mut items = Vec::new();
+    loop {
+        let response = client
+            .post(api_endpoint(
+                &Configuration::api_base_url(),
+                COMPLETED_ENDPOINT,
+            ))
+            .bearer_auth(&auth_token)
+            .form(&[
+                ("limit", limit.as_str()),
+                ("offset", &offset.to_string()),
+                ("annotate_items", "true"),
+            ])
+            .send()?;
+
+        common_response_handler(&response)?;
+
+        let body = response.text()?;
+        let page: types::CompletedStruct = serde_json::from_str(&body)?;
+        let count = page.items.len();
+
+        items.extend(page.items.into_iter().filter_map(|completed| {
+            let mut item = completed.item_object?;
+            item.checked = true;
+            item.completed_at = Some(completed.completed_at);
+            Some(item)
+        }));
+
+        if count < COMPLETED_PAGE_SIZE {
+            return Ok(items);
+        }
+        offset += count;
+    }
+}
 
-    // Map the resource type to the proper string.
-    let resource_string = to_resource_type(resource_type);
+fn execute_request(sync_token: &str) -> Result<types::SyncStruct, TodrError> {
+    // Map the resource types to the proper string.
+    let resource_string = to_resource_types(&SYNCED_RESOURCES);
 
     // Setup the request parameters.
     //
     // See the API documentation available for the full
     // list of parameters, their values and what they do.
     //
-    // Link: https://developer.todoist.com/sync/v8/?shell
+    // Link: https://developer.todoist.com/sync/v9/#read-resources
     //
-    let sync_token: String = "*".to_string();
-    let all_data: String = "true".to_string();
+    // Passing the sync token from the previous response makes the
+    // server only send the resources which changed since then.
     let mut params = HashMap::new();
-    params.insert("token", &auth_token);
-    params.insert("sync_token", &sync_token);
+    params.insert("sync_token", sync_token);
     params.insert("resource_types", &resource_string);
-    params.insert("all_data", &all_data);
 
-    let client = reqwest::blocking::Client::new();
+    post_sync(&params)
+}
 
-    // Issue the request.
-    client.get(TODOIST_API).query(&params).send()
+/// Send a batch of commands to the server.
+///
+/// The request also carries our sync token, so the response holds
+/// the changes the commands made along with their results.
+fn execute_commands(
+    sync_token: &str,
+    commands: &[types::CommandStruct],
+) -> Result<types::SyncStruct, TodrError> {
+    let resource_string = to_resource_types(&SYNCED_RESOURCES);
+    let commands_string = serde_json::to_string(commands)?;
+
+    let mut params = HashMap::new();
+    params.insert("sync_token", sync_token);
+    params.insert("resource_types", &resource_string);
+    params.insert("commands", &commands_string);
+
+    post_sync(&params)
+}
+
+/// Build a new command with a freshly generated uuid.
+fn new_command(command_type: &str, args: serde_json::Value) -> types::CommandStruct {
+    types::CommandStruct {
+        command_type: String::from(command_type),
+        uuid: Uuid::new_v4().to_string(),
+        temp_id: None,
+        args,
+    }
 }
 
 //
 // Request response handler implementations.
 //
 
-fn process_error(error: &reqwest::Error) {
-    println!("{}", error);
+fn common_response_handler(response: &reqwest::blocking::Response) -> Result<(), TodrError> {
+    let status = response.status();
+    if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+        return Err(TodrError::Auth);
+    }
+
+    if !status.is_success() {
+        return Err(TodrError::HttpStatus(status));
+    }
+
+    Ok(())
 }
 
-fn common_response_handler(response: &mut reqwest::blocking::Response) {
-    // Only print these traces in debug mode.
-    if cfg!(debug_assertions) {
-        println!("Headers:\n{:?}", response.headers());
+/// Run a batch of commands against the server and merge their changes.
+///
+/// Returns the mapping from the temporary ids of created objects to
+/// their real ids, or the failures if any of the commands failed.
+fn send_commands(
+    state: &mut State,
+    commands: &[types::CommandStruct],
+) -> Result<HashMap<String, String>, TodrError> {
+    let mut sync_state = execute_commands(&state.sync_token, commands)?;
+
+    let sync_status = sync_state.sync_status.take().unwrap_or_default();
+    let temp_id_mapping = sync_state.temp_id_mapping.take().unwrap_or_default();
+
+    // Commands which did succeed have changed the server state, so
+    // their changes are merged regardless of any failures.
+    state.merge(sync_state);
+    cache::save(state);
+
+    let failures = command_failures(commands, &sync_status);
+    if failures.is_empty() {
+        Ok(temp_id_mapping)
+    } else {
+        Err(TodrError::Command(failures.join("\n")))
     }
 }
 
-fn process_response_items(mut response: reqwest::blocking::Response) {
-    common_response_handler(&mut response);
+/// Describe the commands of a batch which the server reports as failed.
+fn command_failures(
+    commands: &[types::CommandStruct],
+    sync_status: &HashMap<String, types::CommandStatus>,
+) -> Vec<String> {
+    commands
+        .iter()
+        .filter_map(|command| match sync_status.get(&command.uuid) {
+            Some(types::CommandStatus::Error { error, .. }) => Some(format!(
+                "Command {} failed: {}",
+                command.command_type, error
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Bring the local state up to date with the server.
+pub fn sync(state: &mut State) -> Result<(), TodrError> {
+    let sync_state = execute_request(&state.sync_token)?;
+
+    state.merge(sync_state);
+    cache::save(state);
+    Ok(())
+}
+
+/// Sync the state, unless it has already been seeded from the cache.
+fn ensure_synced(state: &mut State) -> Result<(), TodrError> {
+    if state.is_synced() {
+        Ok(())
+    } else {
+        sync(state)
+    }
+}
+
+/// A sync which is running on a background thread.
+#[derive(Debug)]
+pub struct BackgroundSync {
+    /// The sync token the request was issued with.
+    sync_token: String,
+
+    /// Receives the response once the request has completed.
+    receiver: mpsc::Receiver<types::SyncStruct>,
+}
+
+/// Start a sync of the state on a background thread.
+pub fn start_background_sync(state: &State) -> BackgroundSync {
+    let (sender, receiver) = mpsc::channel();
+    let sync_token = state.sync_token.clone();
+    let request_token = sync_token.clone();
+
+    thread::spawn(move || {
+        // Failures are silently dropped, the next
+        // foreground sync will report them instead.
+        if let Ok(sync_state) = execute_request(&request_token) {
+            let _ = sender.send(sync_state);
+        }
+    });
+
+    BackgroundSync {
+        sync_token,
+        receiver,
+    }
+}
+
+/// Merge the result of a background sync into the state if it has completed.
+///
+/// Returns `false` while the sync is still in flight.
+pub fn finish_background_sync(state: &mut State, background: &BackgroundSync) -> bool {
+    match background.receiver.try_recv() {
+        Ok(sync_state) => {
+            // Another sync may have completed in the meantime, in which
+            // case this response is older than what we already have.
+            if state.sync_token == background.sync_token {
+                state.merge(sync_state);
+                cache::save(state);
+            }
+
+            true
+        }
+        Err(mpsc::TryRecvError::Empty) => false,
+        Err(mpsc::TryRecvError::Disconnected) => true,
+    }
+}
+
+//
+// Render implementations.
+//
+
+/// The items which are still active.
+///
+/// Completed items stay in the state until they are deleted.
+fn active_items(state: &State) -> Vec<&types::ItemStruct> {
+    state.items.iter().filter(|i| !i.checked).collect()
+}
 
-    let sync_state: types::SyncStruct = response
-        .json()
-        .expect("Failed to de-serialize JSON response");
+fn render_items(
+    state: &State,
+    items: &[&types::ItemStruct],
+    options: &ListOptions,
+) -> Result<(), TodrError> {
+    let clock = Clock::for_user(state.user.as_ref());
+    let items: Vec<&types::ItemStruct> =
+        items.iter().filter(|i| options.shows(i)).copied().collect();
 
-    let mut items = sync_state.items.expect("Failed to parse items JSON");
+    let mut outputs = Vec::new();
 
-    // Sort the items by their server order.
-    let custom_sort = |a: &types::ItemStruct, b: &types::ItemStruct| {
-        let item_order_sort = a.item_order.cmp(&b.item_order);
-        let project_order_sort = a.project_id.cmp(&b.project_id);
+    // child_order values are only unique among siblings, so if we want to
+    // display items in the correct order we need to group them by project
+    // first, and then walk the hierarchy of each project.
+    for project_id in project_order(state, &items) {
+        let project_items: Vec<&types::ItemStruct> = items
+            .iter()
+            .filter(|i| i.project_id == project_id)
+            .copied()
+            .collect();
 
-        // item_order values are only unique per project, so if we want to
-        // display items in the correct order we need to sort by project
-        // groupings first, and then sort by item order.
-        if project_order_sort == Ordering::Equal {
-            item_order_sort
+        if project_items.is_empty() {
+            continue;
+        }
+
+        if options.format != OutputFormat::Text {
+            for (position, item) in hierarchy_order(&project_items) {
+                outputs.push(ItemOutput::new(state, &clock, item, &position));
+            }
+            continue;
+        }
+
+        // Each group is headed by its project, unless the project
+        // is shown inline with each of the items instead.
+        let project = state.project(project_id);
+        let inline_project = if options.inline_project {
+            project
         } else {
-            project_order_sort
+            if let Some(project) = project {
+                renderer::render_project_heading(project)?;
+            }
+            None
+        };
+
+        for (position, item) in hierarchy_order(&project_items) {
+            let notes = state.notes_on_item(&item.id).len();
+            renderer::render_item(
+                item,
+                &position,
+                &state.labels,
+                inline_project,
+                notes,
+                &clock,
+            )?;
         }
-    };
+    }
+
+    output::print(options.format, &outputs)
+}
+
+/// Order the items by project, and depth first within each project.
+fn ordered_items<'a>(
+    state: &'a State,
+    items: &[&'a types::ItemStruct],
+) -> Vec<(TreePosition, &'a types::ItemStruct)> {
+    let mut ordered = Vec::with_capacity(items.len());
+    for project_id in project_order(state, items) {
+        let project_items: Vec<&types::ItemStruct> = items
+            .iter()
+            .filter(|i| i.project_id == project_id)
+            .copied()
+            .collect();
+
+        ordered.extend(hierarchy_order(&project_items));
+    }
+
+    ordered
+}
 
-    items.sort_by(custom_sort);
+/// The ids of the projects of the given items, in the order they are shown.
+fn project_order<'a>(state: &'a State, items: &[&'a types::ItemStruct]) -> Vec<&'a str> {
+    let projects: Vec<&types::ProjectStruct> = state.projects.iter().collect();
+    let mut project_ids: Vec<&str> = hierarchy_order(&projects)
+        .into_iter()
+        .map(|(_, p)| p.id.as_str())
+        .collect();
 
+    // Items may reference projects we don't know about yet.
     for item in items {
-        renderer::render_item(&item);
+        if !project_ids.contains(&item.project_id.as_str()) {
+            project_ids.push(&item.project_id);
+        }
     }
+
+    project_ids
 }
 
-fn process_response_projects(mut response: reqwest::blocking::Response) {
-    common_response_handler(&mut response);
+fn render_projects(state: &State, options: &ListOptions) -> Result<(), TodrError> {
+    let projects: Vec<&types::ProjectStruct> =
+        state.projects.iter().filter(|p| !p.is_archived).collect();
 
-    let sync_state: types::SyncStruct = response
-        .json()
-        .expect("Failed to deserialize json response");
+    // Show the projects in their server order, nested below their parents.
+    let ordered = hierarchy_order(&projects);
 
-    // Sort the items by their server order.
-    //
-    let mut projects = sync_state.projects.expect("Failed to parse projects JSON");
-    projects.sort_by_key(|p| p.item_order);
+    if options.format != OutputFormat::Text {
+        let outputs: Vec<ProjectOutput> = ordered
+            .iter()
+            .map(|(position, project)| ProjectOutput::new(state, project, position))
+            .collect();
+        return output::print(options.format, &outputs);
+    }
+
+    for (position, project) in ordered {
+        renderer::render_project(project, &position)?;
+    }
+
+    Ok(())
+}
+
+fn render_filters(state: &State, options: &ListOptions) -> Result<(), TodrError> {
+    let mut filters: Vec<&types::FilterStruct> = state.filters.iter().collect();
+    filters.sort_by_key(|f| f.item_order);
+
+    if options.format != OutputFormat::Text {
+        return output::print(options.format, &filters);
+    }
+
+    for filter in filters {
+        renderer::render_filter(filter)?;
+    }
 
-    for project in projects {
-        renderer::render_project(&project);
+    Ok(())
+}
+
+/// The active items due within the given days, ordered the way the
+/// todoist day views order them.
+fn items_due_on<'a>(
+    state: &'a State,
+    clock: &Clock,
+    options: &ListOptions,
+    days: impl Fn(NaiveDate) -> bool,
+) -> Vec<&'a types::ItemStruct> {
+    let mut items: Vec<(Due, &types::ItemStruct)> = active_items(state)
+        .into_iter()
+        .filter(|item| options.shows(item))
+        .filter_map(|item| clock.due(item).map(|due| (due, item)))
+        .filter(|(due, _)| days(due.date()))
+        .collect();
+
+    // Items the user has ordered by hand come first, the others, with a
+    // day order of -1, follow by the time they are due, with all-day
+    // items at the end of a day.
+    items.sort_by_key(|(due, item)| {
+        let day_order = if item.day_order < 0 {
+            i64::MAX
+        } else {
+            item.day_order
+        };
+        (due.date(), day_order, *due, item.child_order)
+    });
+    items.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Render groups of items without their hierarchy, as the day views do.
+///
+/// Machine readable output has no headings, so the groups are joined.
+fn render_days(
+    state: &State,
+    clock: &Clock,
+    days: &[(String, Vec<&types::ItemStruct>)],
+    options: &ListOptions,
+) -> Result<(), TodrError> {
+    if options.format != OutputFormat::Text {
+        let outputs: Vec<ItemOutput> = days
+            .iter()
+            .flat_map(|(_, items)| items)
+            .map(|item| ItemOutput::new(state, clock, item, &TreePosition::default()))
+            .collect();
+        return output::print(options.format, &outputs);
+    }
+
+    for (heading, items) in days {
+        renderer::render_heading(heading)?;
+
+        for item in items {
+            let project = state
+                .project(&item.project_id)
+                .filter(|_| options.inline_project);
+            let notes = state.notes_on_item(&item.id).len();
+            renderer::render_item(
+                item,
+                &TreePosition::default(),
+                &state.labels,
+                project,
+                notes,
+                clock,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn render_labels(state: &State, options: &ListOptions) -> Result<(), TodrError> {
+    let mut labels: Vec<&types::LabelStruct> = state.labels.iter().collect();
+    labels.sort_by_key(|l| l.item_order);
+
+    if options.format != OutputFormat::Text {
+        return output::print(options.format, &labels);
+    }
+
+    for label in labels {
+        renderer::render_label(label)?;
+    }
+
+    Ok(())
+}
+
+//
+// Command dispatch.
+//
+
+/// Options of the listing commands, given before or after their arguments,
+/// e.g. `items --min-priority p2 @work`.
+#[derive(Debug, Default)]
+pub struct ListOptions {
+    /// Only show items with at least this priority, as the API value
+    /// where 4 is `p1`.
+    min_priority: Option<u8>,
+
+    /// Show the project of each item inline, instead of grouping
+    /// the items under a heading for each project.
+    inline_project: bool,
+
+    /// How the listing is printed.
+    format: OutputFormat,
+}
+
+impl ListOptions {
+    /// Split the options from the arguments of a command.
+    fn parse(args: &str, format: OutputFormat) -> Result<(Self, String), TodrError> {
+        let mut options = Self {
+            format,
+            ..Self::default()
+        };
+        let mut rest = Vec::new();
+        let mut words = args.split_whitespace();
+
+        while let Some(word) = words.next() {
+            let (name, value) = match word.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value)),
+                _ => (word, None),
+            };
+
+            match name {
+                "--min-priority" => {
+                    let value = value.or_else(|| words.next()).unwrap_or_default();
+                    options.min_priority = Some(parse_priority(value)?);
+                }
+                "--inline-project" => options.inline_project = true,
+                "--format" => {
+                    let value = value.or_else(|| words.next()).unwrap_or_default();
+                    options.format = OutputFormat::parse(value)?;
+                }
+                _ if name.starts_with("--") => {
+                    return Err(TodrError::Usage(format!("Unknown option: {name}")));
+                }
+                _ => rest.push(word),
+            }
+        }
+
+        Ok((options, rest.join(" ")))
+    }
+
+    /// Whether the item should be listed.
+    fn shows(&self, item: &types::ItemStruct) -> bool {
+        self.min_priority
+            .is_none_or(|min_priority| item.priority >= min_priority)
+    }
+}
+
+/// Parse a priority as shown to the user, `p1` to `p4` or just the number,
+/// into the value the API uses.
+fn parse_priority(priority: &str) -> Result<u8, TodrError> {
+    let level = priority.strip_prefix(['p', 'P']).unwrap_or(priority);
+    match level.parse::<u8>() {
+        Ok(level @ 1..=4) => Ok(5 - level),
+        _ => Err(TodrError::Usage(format!(
+            "Invalid priority, expected p1 to p4: {priority}"
+        ))),
+    }
+}
+
+/// The commands which can be run from both the REPL and the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    Items,
+    Projects,
+    Labels,
+    Filters,
+    Filter,
+    Today,
+    Overdue,
+    Upcoming,
+    Sync,
+    Add,
+    Done,
+    Undone,
+    Remove,
+    Export,
+    Import,
+    Notes,
+    Comment,
+    Reminders,
+    Remind,
+    Unremind,
+}
+
+impl Command {
+    /// Map a command name, or its short alias, to the command.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "help" | "h" => Some(Self::Help),
+            "items" | "i" => Some(Self::Items),
+            "projs" | "p" => Some(Self::Projects),
+            "labels" | "l" => Some(Self::Labels),
+            "filters" | "f" => Some(Self::Filters),
+            "filter" => Some(Self::Filter),
+            "today" => Some(Self::Today),
+            "overdue" => Some(Self::Overdue),
+            "upcoming" => Some(Self::Upcoming),
+            "sync" | "s" => Some(Self::Sync),
+            "add" | "a" => Some(Self::Add),
+            "done" => Some(Self::Done),
+            "undone" => Some(Self::Undone),
+            "rm" => Some(Self::Remove),
+            "export" => Some(Self::Export),
+            "import" => Some(Self::Import),
+            "notes" => Some(Self::Notes),
+            "comment" => Some(Self::Comment),
+            "reminders" => Some(Self::Reminders),
+            "remind" => Some(Self::Remind),
+            "unremind" => Some(Self::Unremind),
+            _ => None,
+        }
+    }
+
+    /// Whether the command splits off its first argument, which has
+    /// to be quoted when it contains spaces.
+    pub const fn has_target(self) -> bool {
+        matches!(self, Self::Comment | Self::Remind)
+    }
+
+    /// Whether the command only renders the local state.
+    pub const fn is_listing(self) -> bool {
+        matches!(
+            self,
+            Self::Items
+                | Self::Projects
+                | Self::Labels
+                | Self::Filters
+                | Self::Filter
+                | Self::Today
+                | Self::Overdue
+                | Self::Upcoming
+                | Self::Notes
+                | Self::Reminders
+        )
+    }
+}
+
+/// Execute a command with the rest of the line as its arguments.
+///
+/// Listings are printed in the given format, unless overridden with `--format`.
+pub fn execute_command(
+    state: &mut State,
+    command: Command,
+    args: &str,
+    format: OutputFormat,
+) -> Result<(), TodrError> {
+    let (options, args) = if command.is_listing() {
+        ListOptions::parse(args, format)?
+    } else {
+        let options = ListOptions {
+            format,
+            ..ListOptions::default()
+        };
+        (options, String::from(args))
+    };
+    let args = args.as_str();
+
+    match command {
+        Command::Help => {
+            help_command();
+            Ok(())
+        }
+        Command::Items => items_command(state, args, &options),
+        Command::Projects => projects_command(state, &options),
+        Command::Labels => labels_command(state, &options),
+        Command::Filters => filters_command(state, &options),
+        Command::Filter => filter_command(state, args, &options),
+        Command::Today => today_command(state, &options),
+        Command::Overdue => overdue_command(state, &options),
+        Command::Upcoming => upcoming_command(state, args, &options),
+        Command::Sync => sync_command(state),
+        Command::Add => add_command(state, args),
+        Command::Done => done_command(state, args, &options),
+        Command::Undone => undone_command(state, args, &options),
+        Command::Remove => remove_command(state, args, &options),
+        Command::Export => export_command(state, args),
+        Command::Import => import_command(state, args),
+        Command::Notes => notes_command(state, args, &options),
+        Command::Comment => comment_command(state, args),
+        Command::Reminders => reminders_command(state, &options),
+        Command::Remind => remind_command(state, args),
+        Command::Unremind => unremind_command(state, args),
     }
 }
 
@@ -140,38 +818,1147 @@ fn process_response_projects(mut response: reqwest::blocking::Response) {
 // Command handler implementations.
 //
 
-pub fn items_command() {
-    let response = execute_request(&TodrResourceType::Items);
+pub fn items_command(
+    state: &mut State,
+    query: &str,
+    options: &ListOptions,
+) -> Result<(), TodrError> {
+    if query.is_empty() {
+        ensure_synced(state)?;
+        return render_items(state, &active_items(state), options);
+    }
+
+    let query = Query::parse(query)?;
+    ensure_synced(state)?;
+    render_query(state, &query, options)
+}
+
+/// Render the active items matching a query.
+fn render_query(state: &State, query: &Query, options: &ListOptions) -> Result<(), TodrError> {
+    let context = Context::new(state);
+    let items: Vec<&types::ItemStruct> = active_items(state)
+        .into_iter()
+        .filter(|i| query.matches(i, &context))
+        .collect();
+
+    render_items(state, &items, options)
+}
+
+pub fn projects_command(state: &mut State, options: &ListOptions) -> Result<(), TodrError> {
+    ensure_synced(state)?;
+    render_projects(state, options)
+}
+
+pub fn labels_command(state: &mut State, options: &ListOptions) -> Result<(), TodrError> {
+    ensure_synced(state)?;
+    render_labels(state, options)
+}
+
+pub fn filters_command(state: &mut State, options: &ListOptions) -> Result<(), TodrError> {
+    ensure_synced(state)?;
+    render_filters(state, options)
+}
+
+pub fn filter_command(
+    state: &mut State,
+    name: &str,
+    options: &ListOptions,
+) -> Result<(), TodrError> {
+    if name.is_empty() {
+        return Err(TodrError::Usage(String::from("Usage: filter <name>")));
+    }
+
+    ensure_synced(state)?;
+
+    let Some(filter) = state
+        .filters
+        .iter()
+        .find(|f| f.name.eq_ignore_ascii_case(name))
+    else {
+        return Err(TodrError::Usage(format!("Unknown filter: {name}")));
+    };
+
+    if let Ok(query) = Query::parse(&filter.query) {
+        return render_query(state, &query, options);
+    }
+
+    // Queries we can't evaluate locally are left to the server, which
+    // may match items we haven't seen yet, so the state is synced first.
+    let query = filter.query.clone();
+    sync(state)?;
+
+    let ids = fetch_filter_item_ids(&query)?;
+    let items: Vec<&types::ItemStruct> = active_items(state)
+        .into_iter()
+        .filter(|i| ids.contains(&i.id))
+        .collect();
+
+    render_items(state, &items, options)
+}
+
+pub fn today_command(state: &mut State, options: &ListOptions) -> Result<(), TodrError> {
+    ensure_synced(state)?;
+
+    let clock = Clock::for_user(state.user.as_ref());
+    let today = clock.today();
+
+    // Like the todoist Today view, overdue items are listed above today's.
+    let mut days = Vec::new();
+    let overdue = items_due_on(state, &clock, options, |date| date < today);
+    if !overdue.is_empty() {
+        days.push((String::from("Overdue"), overdue));
+    }
+
+    let items = items_due_on(state, &clock, options, |date| date == today);
+    days.push((String::from("Today"), items));
+
+    render_days(state, &clock, &days, options)
+}
+
+pub fn overdue_command(state: &mut State, options: &ListOptions) -> Result<(), TodrError> {
+    ensure_synced(state)?;
+
+    let clock = Clock::for_user(state.user.as_ref());
+    let mut items = items_due_on(state, &clock, options, |date| date <= clock.today());
+    items.retain(|item| clock.due(item).is_some_and(|due| clock.is_overdue(due)));
+
+    render_days(state, &clock, &[(String::from("Overdue"), items)], options)
+}
+
+/// Parse the number of days to list, as in `upcoming 3` or `upcoming 3 days`.
+fn parse_upcoming_days(args: &str) -> Option<i64> {
+    let args = args.trim();
+    let count = args
+        .strip_suffix("days")
+        .or_else(|| args.strip_suffix("day"))
+        .unwrap_or(args)
+        .trim();
+
+    match count {
+        "" => Some(7),
+        count => count
+            .parse()
+            .ok()
+            .filter(|days| (1..=MAX_UPCOMING_DAYS).contains(days)),
+    }
+}
+
+pub fn upcoming_command(
+    state: &mut State,
+    args: &str,
+    options: &ListOptions,
+) -> Result<(), TodrError> {
+    let days = parse_upcoming_days(args).ok_or_else(|| {
+        TodrError::Usage(format!(
+            "Usage: upcoming [days], up to {MAX_UPCOMING_DAYS} days"
+        ))
+    })?;
+
+    ensure_synced(state)?;
+
+    let clock = Clock::for_user(state.user.as_ref());
+    let mut upcoming = Vec::new();
+    for offset in 0..days {
+        let date = clock.today() + Duration::days(offset);
+        let items = items_due_on(state, &clock, options, |due| due == date);
+        if items.is_empty() {
+            continue;
+        }
+
+        let heading = match offset {
+            0 => format!("Today, {}", date.format("%a %-d %b")),
+            1 => format!("Tomorrow, {}", date.format("%a %-d %b")),
+            _ => date.format("%a %-d %b").to_string(),
+        };
+        upcoming.push((heading, items));
+    }
+
+    render_days(state, &clock, &upcoming, options)
+}
+
+pub fn sync_command(state: &mut State) -> Result<(), TodrError> {
+    sync(state)?;
+
+    println!(
+        "Synced {} items and {} projects.",
+        state.items.len(),
+        state.projects.len()
+    );
+    Ok(())
+}
+
+pub fn add_command(state: &mut State, content: &str) -> Result<(), TodrError> {
+    if content.is_empty() {
+        return Err(TodrError::Usage(String::from("Usage: add <task content>")));
+    }
+
+    let mut args = json!({ "content": content });
+
+    // Without a project the server adds the item to the inbox.
+    if let Some(project_name) = Configuration::default_project() {
+        match find_project(state, &project_name) {
+            Some(project) => args["project_id"] = json!(project.id),
+            None => println!("Unknown default project {project_name}, adding to the inbox."),
+        }
+    }
+
+    let temp_id = Uuid::new_v4().to_string();
+    let mut command = new_command("item_add", args);
+    command.temp_id = Some(temp_id.clone());
+
+    let temp_id_mapping = send_commands(state, &[command])?;
+
+    if let Some(id) = temp_id_mapping.get(&temp_id) {
+        println!("Added task {id}: {content}");
+    }
+
+    Ok(())
+}
+
+pub fn export_command(state: &mut State, args: &str) -> Result<(), TodrError> {
+    let export = ExportArgs::parse(args)?;
+
+    ensure_synced(state)?;
+
+    let (contents, count, exported) = match export.exported {
+        Exported::Items => {
+            // The synced state only holds the items completed since the
+            // first sync, the others have to be asked for separately.
+            let completed = if export.completed {
+                fetch_completed_items()?
+            } else {
+                Vec::new()
+            };
+
+            let clock = Clock::for_user(state.user.as_ref());
+            let items: Vec<&types::ItemStruct> = state
+                .items
+                .iter()
+                .filter(|i| export.includes(i))
+                .chain(
+                    completed
+                        .iter()
+                        .filter(|c| !state.items.iter().any(|i| i.id == c.id)),
+                )
+                .collect();
+            let items = ordered_items(state, &items);
+            let contents = export::items(&export, state, &clock, &items);
+            (contents, items.len(), "items")
+        }
+        Exported::Projects => {
+            let projects: Vec<&types::ProjectStruct> = state.projects.iter().collect();
+            let projects = hierarchy_order(&projects);
+            let contents = export::projects_csv(state, &projects);
+            (contents, projects.len(), "projects")
+        }
+    };
+
+    fs::write(&export.path, contents).map_err(|e| TodrError::File(export.path.clone(), e))?;
+
+    println!("Exported {count} {exported} to {}", export.path);
+    Ok(())
+}
+
+pub fn import_command(state: &mut State, args: &str) -> Result<(), TodrError> {
+    let usage = || TodrError::Usage(String::from("Usage: import todotxt [--dry-run] <file>"));
+
+    let args = args.strip_prefix("todotxt ").ok_or_else(usage)?.trim();
+    let (dry_run, path) = args
+        .strip_prefix("--dry-run")
+        .map_or((false, args), |path| (true, path.trim()));
+
+    if path.is_empty() {
+        return Err(usage());
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| TodrError::File(String::from(path), e))?;
+
+    // Lines of only tags, such as `+Home @phone`, would add empty items.
+    let mut tasks = Vec::new();
+    for (line, task) in todotxt::parse(&contents) {
+        if task.description.is_empty() {
+            println!("Skipping line {line}, it has no description.");
+        } else {
+            tasks.push(task);
+        }
+    }
+
+    // The cache may predate projects created elsewhere, which
+    // would otherwise be created a second time.
+    sync(state)?;
+
+    // Items can only be in a single project, so the first one is used.
+    let mut new_projects: Vec<&str> = Vec::new();
+    for name in tasks.iter().filter_map(|task| task.projects.first()) {
+        let known = find_project(state, name).is_some()
+            || new_projects.iter().any(|p| p.eq_ignore_ascii_case(name));
+        if !known {
+            new_projects.push(name);
+        }
+    }
+
+    if dry_run {
+        for name in &new_projects {
+            println!("Would create project {name}");
+        }
+        for task in &tasks {
+            println!("Would add {}", describe_todotxt_task(task));
+        }
+        println!(
+            "{} items and {} new projects would be imported.",
+            tasks.len(),
+            new_projects.len()
+        );
+        return Ok(());
+    }
+
+    // The projects are created up front, so that the items of every
+    // later batch can refer to them by their real ids.
+    let project_commands: Vec<types::CommandStruct> = new_projects
+        .iter()
+        .map(|name| {
+            let mut command = new_command("project_add", json!({ "name": name }));
+            command.temp_id = Some(Uuid::new_v4().to_string());
+            command
+        })
+        .collect();
+    for batch in project_commands.chunks(MAX_BATCH_COMMANDS) {
+        send_commands(state, batch)?;
+    }
+
+    let default_project = Configuration::default_project();
+    let mut batch = Vec::new();
+    for task in &tasks {
+        let mut args = json!({
+            "content": task.description,
+            "priority": task.api_priority(),
+            "labels": task.contexts,
+        });
+
+        // Like `add`, items without a project go to the default project.
+        let project_name = task.projects.first().or(default_project.as_ref());
+        if let Some(project) = project_name.and_then(|name| find_project(state, name)) {
+            args["project_id"] = json!(project.id);
+        }
+
+        if let Some(due) = task.due {
+            args["due"] = json!({ "date": due.format("%Y-%m-%d").to_string() });
+        }
+
+        let temp_id = Uuid::new_v4().to_string();
+        let mut commands = vec![new_command("item_add", args)];
+        commands[0].temp_id = Some(temp_id.clone());
+
+        // Completing the new item refers to it by its temporary id,
+        // so both commands have to be sent in the same batch.
+        if task.completed {
+            let mut args = json!({ "id": temp_id });
+            if let Some(completed) = task.completion_date {
+                args["date_completed"] =
+                    json!(format!("{}T00:00:00Z", completed.format("%Y-%m-%d")));
+            }
+            commands.push(new_command("item_complete", args));
+        }
+
+        if batch.len() + commands.len() > MAX_BATCH_COMMANDS {
+            send_commands(state, &batch)?;
+            batch.clear();
+        }
+        batch.extend(commands);
+    }
+
+    if !batch.is_empty() {
+        send_commands(state, &batch)?;
+    }
+
+    println!(
+        "Imported {} items and created {} projects.",
+        tasks.len(),
+        new_projects.len()
+    );
+    Ok(())
+}
+
+/// Describe an imported task the way it would be added.
+fn describe_todotxt_task(task: &todotxt::Task) -> String {
+    let mut words = vec![format!("p{}", 5 - task.api_priority())];
+    if task.completed {
+        words.push(String::from("completed"));
+    }
+
+    words.push(task.description.clone());
+    words.extend(task.projects.first().map(|project| format!("#{project}")));
+    words.extend(task.contexts.iter().map(|context| format!("@{context}")));
+    words.extend(task.due.map(|due| format!("(due {due})")));
+
+    words.join(" ")
+}
+
+/// Find a project by its name, ignoring case.
+fn find_project<'a>(state: &'a State, name: &str) -> Option<&'a types::ProjectStruct> {
+    state
+        .projects
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+/// Resolve an item from either its id or a unique part of its content.
+fn resolve_item<P>(state: &State, query: &str, predicate: P) -> Result<String, TodrError>
+where
+    P: Fn(&types::ItemStruct) -> bool,
+{
+    if query.is_empty() {
+        return Err(TodrError::Usage(String::from(
+            "Please specify an item, either by id or by its content.",
+        )));
+    }
+
+    if state.items.iter().any(|i| i.id == query) {
+        return Ok(String::from(query));
+    }
+
+    let needle = query.to_lowercase();
+    let matches: Vec<&types::ItemStruct> = state
+        .items
+        .iter()
+        .filter(|i| predicate(i) && i.content.to_lowercase().contains(&needle))
+        .collect();
+
+    match matches.as_slice() {
+        // Items which aren't known locally, such as completed
+        // ones, can still be referenced by their id.
+        [] if query.chars().all(|c| c.is_ascii_digit()) => Ok(String::from(query)),
+        [] => Err(TodrError::Usage(format!("No item matches: {query}"))),
+        [item] => Ok(item.id.clone()),
+        _ => {
+            let candidates: Vec<String> = matches
+                .iter()
+                .map(|item| format!("  {} {}", item.id, item.content))
+                .collect();
+            Err(TodrError::Usage(format!(
+                "Multiple items match, please use the id instead:\n{}",
+                candidates.join("\n")
+            )))
+        }
+    }
+}
+
+/// What a note is posted on.
+enum NoteTarget {
+    Item(String),
+    Project(String),
+}
+
+/// Resolve the target of a note, a project when prefixed by `#`, an item otherwise.
+fn resolve_note_target(state: &State, target: &str) -> Result<NoteTarget, TodrError> {
+    target.strip_prefix('#').map_or_else(
+        || resolve_item(state, target, |_| true).map(NoteTarget::Item),
+        |name| {
+            find_project(state, name)
+                .map(|project| NoteTarget::Project(project.id.clone()))
+                .ok_or_else(|| TodrError::Usage(format!("No project named: {name}")))
+        },
+    )
+}
+
+/// Split off the first argument, which may be quoted to contain spaces.
+fn split_first_argument(args: &str) -> (&str, &str) {
+    let quoted = args.strip_prefix('"').and_then(|rest| rest.split_once('"'));
+
+    let (first, rest) = quoted
+        .or_else(|| args.split_once(char::is_whitespace))
+        .unwrap_or((args, ""));
+
+    (first, rest.trim())
+}
+
+pub fn notes_command(
+    state: &mut State,
+    target: &str,
+    options: &ListOptions,
+) -> Result<(), TodrError> {
+    if target.is_empty() {
+        return Err(TodrError::Usage(String::from(
+            "Usage: notes <item>, or notes #<project>",
+        )));
+    }
+
+    ensure_synced(state)?;
+
+    // Unlike for comments there's nothing following the target, so it needn't be quoted.
+    let target = target.trim_matches('"');
+    let (heading, notes) = match resolve_note_target(state, target)? {
+        NoteTarget::Item(id) => {
+            let content = state
+                .items
+                .iter()
+                .find(|i| i.id == id)
+                .map(|i| i.content.clone());
+            (
+                content.unwrap_or_else(|| id.clone()),
+                state.notes_on_item(&id),
+            )
+        }
+        NoteTarget::Project(id) => {
+            let name = state
+                .project(&id)
+                .map(|p| p.name.clone())
+                .unwrap_or_default();
+            (name, state.notes_on_project(&id))
+        }
+    };
+
+    if options.format != OutputFormat::Text {
+        return output::print(options.format, &notes);
+    }
+
+    renderer::render_heading(&heading)?;
+    if notes.is_empty() {
+        println!("  No comments.");
+    }
+
+    let clock = Clock::for_user(state.user.as_ref());
+    for note in notes {
+        let author = note
+            .posted_uid
+            .as_ref()
+            .and_then(|uid| state.user_name(uid))
+            .unwrap_or("Unknown");
+        let posted = note
+            .posted_at
+            .as_ref()
+            .and_then(|t| clock.timestamp(t))
+            .map(|t| t.format("%b %-d %Y %H:%M").to_string());
+
+        renderer::render_note(note, author, posted)?;
+    }
+
+    Ok(())
+}
+
+pub fn comment_command(state: &mut State, args: &str) -> Result<(), TodrError> {
+    let (target, content) = split_first_argument(args);
+    if target.is_empty() || content.is_empty() {
+        return Err(TodrError::Usage(String::from(
+            "Usage: comment <item> <text>, or comment #<project> <text>",
+        )));
+    }
+
+    ensure_synced(state)?;
+
+    let mut command = match resolve_note_target(state, target)? {
+        NoteTarget::Item(id) => {
+            new_command("note_add", json!({ "item_id": id, "content": content }))
+        }
+        NoteTarget::Project(id) => new_command(
+            "project_note_add",
+            json!({ "project_id": id, "content": content }),
+        ),
+    };
+    command.temp_id = Some(Uuid::new_v4().to_string());
+
+    send_commands(state, &[command])?;
+
+    println!("Added comment: {content}");
+    Ok(())
+}
+
+/// When a new reminder goes off.
+#[derive(Debug, PartialEq, Eq)]
+enum ReminderTime {
+    /// A number of minutes before the item is due.
+    Relative(i64),
+
+    /// A time on the users clock.
+    Absolute(NaiveDateTime),
+}
+
+/// Parse the time of a reminder, an offset such as `30m`, `2h` or `1d`,
+/// or a date and time such as `2024-03-11 09:00`.
+fn parse_reminder_time(time: &str) -> Result<ReminderTime, TodrError> {
+    let time = time.trim();
+
+    if let Ok(at) = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M") {
+        return Ok(ReminderTime::Absolute(at));
+    }
+
+    let minutes = match time.char_indices().last() {
+        Some((i, 'm')) => time[..i].parse::<i64>().ok(),
+        Some((i, 'h')) => time[..i]
+            .parse::<i64>()
+            .ok()
+            .and_then(|h| h.checked_mul(60)),
+        Some((i, 'd')) => time[..i]
+            .parse::<i64>()
+            .ok()
+            .and_then(|d| d.checked_mul(24 * 60)),
+        _ => None,
+    };
+
+    match minutes {
+        Some(minutes) if minutes >= 0 => Ok(ReminderTime::Relative(minutes)),
+        _ => Err(TodrError::Usage(format!(
+            "Invalid reminder time, expected e.g. 30m, 2h, 1d or 2024-03-11 09:00: {time}"
+        ))),
+    }
+}
+
+/// Describe the offset of a relative reminder, such as `30 min before`.
+fn describe_minute_offset(minutes: i64) -> String {
+    match minutes {
+        0 => String::from("when due"),
+        _ if minutes % (24 * 60) == 0 => format!("{}d before", minutes / (24 * 60)),
+        _ if minutes % 60 == 0 => format!("{}h before", minutes / 60),
+        _ => format!("{minutes} min before"),
+    }
+}
+
+/// The reminders of the active items which have yet to go off, soonest first.
+fn upcoming_reminders<'a>(
+    state: &'a State,
+    clock: &Clock,
+) -> Vec<(
+    NaiveDateTime,
+    &'a types::ReminderStruct,
+    &'a types::ItemStruct,
+)> {
+    let mut reminders: Vec<_> = state
+        .reminders
+        .iter()
+        .filter_map(|reminder| {
+            let item = state
+                .items
+                .iter()
+                .find(|i| i.id == reminder.item_id && !i.checked)?;
+            let at = clock.reminder_time(reminder, item)?;
+            (at >= clock.now()).then_some((at, reminder, item))
+        })
+        .collect();
+
+    reminders.sort_by_key(|(at, _, _)| *at);
+    reminders
+}
+
+pub fn reminders_command(state: &mut State, options: &ListOptions) -> Result<(), TodrError> {
+    ensure_synced(state)?;
+
+    let clock = Clock::for_user(state.user.as_ref());
+    let reminders = upcoming_reminders(state, &clock);
+
+    if options.format != OutputFormat::Text {
+        let reminders: Vec<ReminderOutput> = reminders
+            .iter()
+            .map(|(at, reminder, item)| ReminderOutput::new(&clock, reminder, item, *at))
+            .collect();
+        return output::print(options.format, &reminders);
+    }
+
+    renderer::render_heading("Reminders")?;
+    if reminders.is_empty() {
+        println!("  No upcoming reminders.");
+    }
+
+    for (at, reminder, item) in reminders {
+        let detail = match reminder.reminder_type {
+            types::ReminderType::Relative => reminder.minute_offset.map(describe_minute_offset),
+            _ => None,
+        };
+
+        renderer::render_reminder(
+            reminder,
+            item,
+            &at.format("%a %b %-d %H:%M").to_string(),
+            detail,
+        )?;
+    }
+
+    Ok(())
+}
 
-    match response {
-        Ok(r) => process_response_items(r),
-        Err(e) => process_error(&e),
+pub fn remind_command(state: &mut State, args: &str) -> Result<(), TodrError> {
+    let (query, time) = split_first_argument(args);
+    if query.is_empty() || time.is_empty() {
+        return Err(TodrError::Usage(String::from(
+            "Usage: remind <item> <30m|2h|1d|YYYY-MM-DD HH:MM>",
+        )));
     }
+
+    let time = parse_reminder_time(time)?;
+    ensure_synced(state)?;
+
+    let id = resolve_item(state, query, |i| !i.checked)?;
+    let clock = Clock::for_user(state.user.as_ref());
+    let item = state.items.iter().find(|i| i.id == id);
+
+    let (args, description) = match time {
+        ReminderTime::Relative(minutes) => {
+            // The server rejects relative reminders on items without a due time.
+            if item.is_some_and(|i| !matches!(clock.due(i), Some(Due::At(_)))) {
+                return Err(TodrError::Usage(String::from(
+                    "Relative reminders need the item to be due at a time.",
+                )));
+            }
+
+            (
+                json!({ "item_id": id, "type": "relative", "minute_offset": minutes }),
+                describe_minute_offset(minutes),
+            )
+        }
+        ReminderTime::Absolute(at) => {
+            let utc = clock.utc(at).ok_or_else(|| {
+                TodrError::Usage(format!(
+                    "Invalid reminder time: {}",
+                    at.format("%Y-%m-%d %H:%M")
+                ))
+            })?;
+            let due = utc.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+            (
+                json!({ "item_id": id, "type": "absolute", "due": { "date": due } }),
+                at.format("%a %b %-d %H:%M").to_string(),
+            )
+        }
+    };
+    let content = item.map_or_else(|| id.clone(), |i| i.content.clone());
+
+    let mut command = new_command("reminder_add", args);
+    command.temp_id = Some(Uuid::new_v4().to_string());
+    send_commands(state, &[command])?;
+
+    println!("Added reminder: {content}, {description}");
+    Ok(())
 }
 
-pub fn projects_command() {
-    let response = execute_request(&TodrResourceType::Projects);
+pub fn unremind_command(state: &mut State, id: &str) -> Result<(), TodrError> {
+    if id.is_empty() {
+        return Err(TodrError::Usage(String::from(
+            "Usage: unremind <reminder id>, as shown by reminders",
+        )));
+    }
+
+    ensure_synced(state)?;
 
-    match response {
-        Ok(r) => process_response_projects(r),
-        Err(e) => process_error(&e),
+    if !state.reminders.iter().any(|r| r.id == id) {
+        return Err(TodrError::Usage(format!("No reminder with id: {id}")));
     }
+
+    send_commands(
+        state,
+        &[new_command("reminder_delete", json!({ "id": id }))],
+    )?;
+
+    println!("Removed reminder {id}.");
+    Ok(())
+}
+
+/// Resolve an item, run a command against it and show the updated items.
+fn modify_item<P>(
+    state: &mut State,
+    query: &str,
+    predicate: P,
+    command_type: &str,
+    build_args: fn(&str) -> serde_json::Value,
+    options: &ListOptions,
+) -> Result<(), TodrError>
+where
+    P: Fn(&types::ItemStruct) -> bool,
+{
+    ensure_synced(state)?;
+
+    let id = resolve_item(state, query, predicate)?;
+    send_commands(state, &[new_command(command_type, build_args(&id))])?;
+
+    render_items(state, &active_items(state), options)
+}
+
+pub fn done_command(
+    state: &mut State,
+    query: &str,
+    options: &ListOptions,
+) -> Result<(), TodrError> {
+    // Closing, rather than completing, moves recurring items
+    // to their next occurrence instead of archiving them.
+    modify_item(
+        state,
+        query,
+        |i| !i.checked,
+        "item_close",
+        |id| json!({ "id": id }),
+        options,
+    )
+}
+
+pub fn undone_command(
+    state: &mut State,
+    query: &str,
+    options: &ListOptions,
+) -> Result<(), TodrError> {
+    modify_item(
+        state,
+        query,
+        |i| i.checked,
+        "item_uncomplete",
+        |id| json!({ "id": id }),
+        options,
+    )
+}
+
+pub fn remove_command(
+    state: &mut State,
+    query: &str,
+    options: &ListOptions,
+) -> Result<(), TodrError> {
+    modify_item(
+        state,
+        query,
+        |_| true,
+        "item_delete",
+        |id| json!({ "id": id }),
+        options,
+    )
 }
 
 pub fn help_command() {
     println!();
     println!("Commands:");
     println!();
-    println!("  h | help  - This help message");
+    println!("  h | help    - This help message");
+    println!();
+    println!("  i | items   - List all active todo items, or those matching a query,");
+    println!("                e.g. `items (today | overdue) & @work`.");
+    println!();
+    println!("  a | add     - Add a new todo item, e.g. `add Buy milk`.");
+    println!();
+    println!("  done        - Complete an item, by id or by part of its content.");
+    println!();
+    println!("  undone      - Uncomplete a previously completed item.");
+    println!();
+    println!("  rm          - Delete an item.");
+    println!();
+    println!("  notes       - Show the comments on an item, or on a project with `#`.");
+    println!();
+    println!("  comment     - Comment on an item, e.g. `comment milk Get oat milk`.");
+    println!("                Quote the item if needed: `comment \"buy milk\" Oat milk`.");
+    println!();
+    println!("  reminders   - List the upcoming reminders, with their ids.");
+    println!();
+    println!("  remind      - Remind of an item, before it's due or at a time, e.g.");
+    println!("                `remind milk 30m`, `remind milk 2024-03-11 09:00`.");
+    println!();
+    println!("  unremind    - Delete a reminder, by its id.");
+    println!();
+    println!("  p | projs   - List all active projects.");
+    println!();
+    println!("  l | labels  - List all labels.");
+    println!();
+    println!("  f | filters - List all saved filters.");
+    println!();
+    println!("  filter      - List the items matching a saved filter, by name.");
+    println!();
+    println!("  today       - List the items due today, and those overdue.");
     println!();
-    println!("  i | items - List all active todo items.");
+    println!("  overdue     - List the overdue items.");
     println!();
-    println!("  p | projs - List all active projects.");
+    println!("  upcoming    - List the items due in the next days, 7 unless given (up to 365).");
     println!();
-    println!("  q | quit  - Exit the application.");
+    println!("  export      - Write the items to a csv, md, org, ics or todotxt file, e.g.");
+    println!("                `export org todo.org`, or `export csv projects projects.csv`.");
+    println!();
+    println!("  import      - Add the tasks of a todo.txt file, e.g. `import todotxt todo.txt`.");
+    println!("                Use `import todotxt --dry-run todo.txt` to preview them first.");
+    println!();
+    println!("  s | sync    - Refresh the local cache from the server.");
+    println!();
+    println!("  format      - Show or set the format of listings: text, json or jsonl.");
+    println!();
+    println!("  q | quit    - Exit the application.");
+    println!();
+    println!("Item listings take `--min-priority p2` to hide the less urgent items,");
+    println!("and `--inline-project` to show the project next to each item.");
+    println!("All listings take `--format json` or `--format jsonl` for scripts.");
     println!();
 }
 
 pub fn unknown_command(command: &str) {
-    println!("Unknown Command: {}", command);
+    println!("Unknown Command: {command}");
+}
+
+#[test]
+fn api_endpoint_test() {
+    assert_eq!(
+        api_endpoint("https://api.todoist.com", SYNC_ENDPOINT),
+        "https://api.todoist.com/sync/v9/sync"
+    );
+
+    // Proxies may be mounted below a path, with or without a trailing slash.
+    assert_eq!(
+        api_endpoint("http://localhost:8080/todoist/", SYNC_ENDPOINT),
+        "http://localhost:8080/todoist/sync/v9/sync"
+    );
+}
+
+#[test]
+fn command_failures_test() {
+    let commands = [
+        new_command("item_add", json!({ "content": "Buy milk" })),
+        new_command("item_close", json!({ "id": "1" })),
+        new_command("item_delete", json!({ "id": "2" })),
+    ];
+
+    let sync_status: HashMap<String, types::CommandStatus> = serde_json::from_value(json!({
+        commands[0].uuid.clone(): "ok",
+        commands[1].uuid.clone(): { "error_code": 22, "error": "Item not found" },
+    }))
+    .unwrap();
+
+    // Commands the server didn't report on aren't counted as failed.
+    assert_eq!(
+        command_failures(&commands, &sync_status),
+        vec!["Command item_close failed: Item not found"]
+    );
+    assert!(command_failures(&commands, &HashMap::new()).is_empty());
+}
+
+#[test]
+fn command_parse_test() {
+    assert_eq!(Command::parse("items"), Some(Command::Items));
+    assert_eq!(Command::parse("i"), Some(Command::Items));
+    assert_eq!(Command::parse("rm"), Some(Command::Remove));
+    assert_eq!(Command::parse("projs"), Some(Command::Projects));
+
+    // Names are matched exactly, the REPL completes partial ones.
+    assert_eq!(Command::parse("item"), None);
+    assert_eq!(Command::parse("Items"), None);
+    assert_eq!(Command::parse(""), None);
+
+    assert!(Command::Today.is_listing());
+    assert!(!Command::Add.is_listing());
+    assert!(!Command::Sync.is_listing());
+}
+
+#[cfg(test)]
+use chrono::FixedOffset;
+
+#[cfg(test)]
+use state::{test_due, test_item};
+
+#[cfg(test)]
+fn test_project(id: &str, parent_id: Option<&str>, child_order: i64) -> serde_json::Value {
+    json!({
+        "id": id, "name": id, "color": "blue", "parent_id": parent_id,
+        "child_order": child_order, "collapsed": false, "shared": false,
+        "inbox_project": false, "is_favorite": false, "is_deleted": false,
+        "is_archived": false
+    })
+}
+
+#[cfg(test)]
+fn test_state(projects: &[serde_json::Value], items: &[types::ItemStruct]) -> State {
+    let sync_state = json!({
+        "full_sync": true, "sync_token": "next", "projects": projects, "items": items
+    });
+
+    let mut state = State::new();
+    state.merge(serde_json::from_value(sync_state).unwrap());
+    state
+}
+
+#[test]
+fn resolve_item_test() {
+    let mut done = test_item("3", "Buy bread");
+    done.checked = true;
+    let state = test_state(
+        &[],
+        &[test_item("1", "Buy milk"), test_item("2", "2024"), done],
+    );
+    let resolve = |query| resolve_item(&state, query, |i| !i.checked);
+
+    assert_eq!(resolve("1").unwrap(), "1");
+    assert_eq!(resolve("milk").unwrap(), "1");
+    assert_eq!(resolve("BUY").unwrap(), "1");
+
+    // Numbers are matched against the content before being taken as an id.
+    assert_eq!(resolve("202").unwrap(), "2");
+    assert_eq!(resolve("4711").unwrap(), "4711");
+
+    assert!(resolve("bread").is_err());
+    assert!(resolve("eggs").is_err());
+    assert!(resolve("").is_err());
+    assert!(resolve_item(&state, "buy", |_| true).is_err());
+}
+
+#[test]
+fn items_due_on_test() {
+    let item = |id: &str, due: &str, day_order: i64| {
+        let mut item = test_item(id, id);
+        item.due = Some(test_due(due));
+        item.day_order = day_order;
+        item
+    };
+    let state = test_state(
+        &[],
+        &[
+            item("all-day", "2024-03-10", -1),
+            item("evening", "2024-03-10T18:00:00", -1),
+            item("morning", "2024-03-10T08:00:00", -1),
+            item("second", "2024-03-10T20:00:00", 2),
+            item("first", "2024-03-10", 1),
+            item("tomorrow", "2024-03-11T07:00:00", 0),
+        ],
+    );
+
+    let now = NaiveDate::from_ymd_opt(2024, 3, 10)
+        .and_then(|d| d.and_hms_opt(12, 0, 0))
+        .unwrap();
+    let clock = Clock::new(now, FixedOffset::east_opt(0).unwrap());
+    let ids = |items: Vec<&types::ItemStruct>| -> Vec<String> {
+        items.into_iter().map(|i| i.id.clone()).collect()
+    };
+
+    // Hand-ordered items come first, then the others by their due time.
+    let today = items_due_on(&state, &clock, &ListOptions::default(), |d| {
+        d == clock.today()
+    });
+    assert_eq!(
+        ids(today),
+        vec!["first", "second", "morning", "evening", "all-day"]
+    );
+
+    // Days come before the order within them.
+    let all = items_due_on(&state, &clock, &ListOptions::default(), |_| true);
+    assert_eq!(ids(all).last().unwrap(), "tomorrow");
+}
+
+#[test]
+fn ordered_items_test() {
+    let item = |id: &str, project_id: &str, parent_id: Option<&str>, child_order: i64| {
+        let mut item = test_item(id, id);
+        item.project_id = String::from(project_id);
+        item.parent_id = parent_id.map(String::from);
+        item.child_order = child_order;
+        item
+    };
+    let state = test_state(
+        &[
+            test_project("work", None, 2),
+            test_project("home", None, 1),
+            test_project("garden", Some("home"), 1),
+        ],
+        &[
+            item("report", "work", None, 1),
+            item("unknown", "shared", None, 1),
+            item("mow", "garden", None, 1),
+            item("milk", "home", None, 2),
+            item("dishes", "home", None, 1),
+            item("rinse", "home", Some("dishes"), 1),
+        ],
+    );
+
+    let items: Vec<&types::ItemStruct> = state.items.iter().collect();
+    let ordered: Vec<(usize, &str)> = ordered_items(&state, &items)
+        .into_iter()
+        .map(|(position, item)| (position.last.len(), item.id.as_str()))
+        .collect();
+
+    // Projects are grouped in their own hierarchy order, with projects
+    // that aren't known locally last, and items nested below their parents.
+    assert_eq!(
+        ordered,
+        vec![
+            (1, "dishes"),
+            (2, "rinse"),
+            (1, "milk"),
+            (1, "mow"),
+            (1, "report"),
+            (1, "unknown"),
+        ]
+    );
+}
+
+#[test]
+fn parse_upcoming_days_test() {
+    assert_eq!(parse_upcoming_days(""), Some(7));
+    assert_eq!(parse_upcoming_days("3"), Some(3));
+    assert_eq!(parse_upcoming_days("3 days"), Some(3));
+    assert_eq!(parse_upcoming_days("1 day"), Some(1));
+    assert_eq!(parse_upcoming_days("365"), Some(365));
+
+    assert_eq!(parse_upcoming_days("0"), None);
+    assert_eq!(parse_upcoming_days("366 days"), None);
+    assert_eq!(parse_upcoming_days("1000000000000"), None);
+    assert_eq!(parse_upcoming_days("week"), None);
+}
+
+#[test]
+fn split_first_argument_test() {
+    assert_eq!(
+        split_first_argument("milk Get oat milk"),
+        ("milk", "Get oat milk")
+    );
+    assert_eq!(
+        split_first_argument("\"buy milk\"  Get oat milk"),
+        ("buy milk", "Get oat milk")
+    );
+    assert_eq!(split_first_argument("#Home"), ("#Home", ""));
+    assert_eq!(split_first_argument(""), ("", ""));
+}
+
+#[test]
+fn parse_reminder_time_test() {
+    let at = NaiveDate::from_ymd_opt(2024, 3, 11)
+        .and_then(|d| d.and_hms_opt(9, 0, 0))
+        .unwrap();
+
+    assert_eq!(
+        parse_reminder_time("30m").unwrap(),
+        ReminderTime::Relative(30)
+    );
+    assert_eq!(
+        parse_reminder_time("2h").unwrap(),
+        ReminderTime::Relative(120)
+    );
+    assert_eq!(
+        parse_reminder_time("1d").unwrap(),
+        ReminderTime::Relative(1440)
+    );
+    assert_eq!(
+        parse_reminder_time("2024-03-11 09:00").unwrap(),
+        ReminderTime::Absolute(at)
+    );
+
+    assert!(parse_reminder_time("-5m").is_err());
+    assert!(parse_reminder_time("30").is_err());
+    assert!(parse_reminder_time("tomorrow").is_err());
+    assert!(parse_reminder_time("9999999999999999d").is_err());
+    assert!(parse_reminder_time("999999999999999999h").is_err());
+
+    assert_eq!(describe_minute_offset(0), "when due");
+    assert_eq!(describe_minute_offset(30), "30 min before");
+    assert_eq!(describe_minute_offset(120), "2h before");
+    assert_eq!(describe_minute_offset(2880), "2d before");
+}
+
+#[test]
+fn list_options_test() {
+    let (options, args) =
+        ListOptions::parse("--min-priority p2 @work & today", OutputFormat::Text).unwrap();
+    assert_eq!(options.min_priority, Some(3));
+    assert_eq!(args, "@work & today");
+
+    let (options, args) =
+        ListOptions::parse("Work --min-priority=1 --inline-project", OutputFormat::Text).unwrap();
+    assert_eq!(options.min_priority, Some(4));
+    assert!(options.inline_project);
+    assert_eq!(args, "Work");
+
+    // The session format applies unless the command overrides it.
+    let (options, _) = ListOptions::parse("@work", OutputFormat::Json).unwrap();
+    assert_eq!(options.format, OutputFormat::Json);
+    let (options, _) = ListOptions::parse("--format jsonl @work", OutputFormat::Json).unwrap();
+    assert_eq!(options.format, OutputFormat::JsonLines);
+    assert!(ListOptions::parse("--format=xml", OutputFormat::Text).is_err());
+
+    assert!(ListOptions::parse("--min-priority p5", OutputFormat::Text).is_err());
+    assert!(ListOptions::parse("--min-priority", OutputFormat::Text).is_err());
+    assert!(ListOptions::parse("--unknown", OutputFormat::Text).is_err());
 }
@@ -0,0 +1,382 @@
+//! This module implements writing items as iCalendar (RFC 5545) components.
+//!
+//! Items are written as to-dos, or as events when they are due at a time
+//! and events were asked for. Recurring items get a recurrence rule when
+//! their todoist due string is simple enough to translate, such as
+//! `every 2 weeks` or `every mon, fri at 9am`.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use dates::Clock;
+use types;
+
+// The maximum length of a content line in octets, excluding the line break.
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Render the items with a due date as an iCalendar file.
+pub fn calendar(clock: &Clock, items: &[&types::ItemStruct], events: bool) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//todr//todr//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+    ];
+
+    let stamp = clock
+        .utc(clock.now())
+        .map(|now| now.format("%Y%m%dT%H%M%SZ").to_string())
+        .unwrap_or_default();
+
+    for item in items {
+        let Some(ref due) = item.due else {
+            continue;
+        };
+        let Some(start) = date_value(&due.date) else {
+            continue;
+        };
+
+        // All-day items are kept as to-dos, as calendars would show
+        // them as events spanning the whole day.
+        let component = if events && !start.starts_with(";VALUE=DATE:") {
+            "VEVENT"
+        } else {
+            "VTODO"
+        };
+
+        lines.push(format!("BEGIN:{component}"));
+        lines.push(format!("UID:{}@todr", item.id));
+        lines.push(format!("DTSTAMP:{stamp}"));
+        lines.push(format!("SUMMARY:{}", escape_text(&item.content)));
+
+        if !item.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape_text(&item.description)));
+        }
+
+        let rule = if due.is_recurring {
+            recurrence_rule(&due.string)
+        } else {
+            None
+        };
+
+        // Recurrences are counted from the start, which a to-do
+        // with a rule must have, so it starts when it is due.
+        if component == "VEVENT" || rule.is_some() {
+            lines.push(format!("DTSTART{start}"));
+        }
+        if component == "VTODO" {
+            lines.push(format!("DUE{start}"));
+        }
+
+        if let Some(priority) = priority(item.priority) {
+            lines.push(format!("PRIORITY:{priority}"));
+        }
+
+        if !item.labels.is_empty() {
+            let categories: Vec<String> = item.labels.iter().map(|l| escape_text(l)).collect();
+            lines.push(format!("CATEGORIES:{}", categories.join(",")));
+        }
+
+        if let Some(rule) = rule {
+            lines.push(format!("RRULE:{rule}"));
+        }
+
+        if component == "VTODO" {
+            if item.checked {
+                lines.push(String::from("STATUS:COMPLETED"));
+
+                let completed = item
+                    .completed_at
+                    .as_ref()
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+                if let Some(completed) = completed {
+                    let completed = completed.with_timezone(&Utc);
+                    lines.push(format!("COMPLETED:{}", completed.format("%Y%m%dT%H%M%SZ")));
+                }
+            } else {
+                lines.push(String::from("STATUS:NEEDS-ACTION"));
+            }
+        }
+
+        lines.push(format!("END:{component}"));
+    }
+
+    lines.push(String::from("END:VCALENDAR"));
+
+    let lines: Vec<String> = lines.iter().map(|line| fold_line(line)).collect();
+    lines.join("\r\n") + "\r\n"
+}
+
+/// The parameters and value of a date property for a todoist due date.
+///
+/// All-day dates are written as dates, floating times without a timezone
+/// so they stay on the wall clock, and fixed times in UTC.
+fn date_value(date: &str) -> Option<String> {
+    if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Some(format!(";VALUE=DATE:{}", date.format("%Y%m%d")));
+    }
+
+    if let Ok(at) = NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S") {
+        return Some(format!(":{}", at.format("%Y%m%dT%H%M%S")));
+    }
+
+    DateTime::parse_from_rfc3339(date)
+        .ok()
+        .map(|at| format!(":{}", at.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")))
+}
+
+/// Map an API priority to the iCalendar one, where 1 is the highest.
+const fn priority(priority: u8) -> Option<u8> {
+    match priority {
+        4 => Some(1),
+        3 => Some(5),
+        2 => Some(9),
+        _ => None,
+    }
+}
+
+/// Escape the characters with a meaning in iCalendar text values.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line into lines of at most 75 octets, each of the
+/// continuation lines starting with a space.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded
+}
+
+/// Translate a todoist recurring due string into an iCalendar recurrence rule.
+///
+/// Only the common English forms are understood, anything else yields `None`
+/// so no rule is written rather than a wrong one.
+pub fn recurrence_rule(string: &str) -> Option<String> {
+    let string = string.trim().to_lowercase();
+
+    // `every!` repeats from the completion date, which a calendar can't express,
+    // and the time of day is already part of the due date.
+    let string = string.replacen("every!", "every", 1);
+    let string = string.split(" at ").next().unwrap_or_default().trim();
+
+    match string {
+        "daily" => return Some(String::from("FREQ=DAILY")),
+        "weekly" => return Some(String::from("FREQ=WEEKLY")),
+        "monthly" => return Some(String::from("FREQ=MONTHLY")),
+        "yearly" | "annually" => return Some(String::from("FREQ=YEARLY")),
+        _ => {}
+    }
+
+    let rest = string.strip_prefix("every ")?.trim();
+
+    let (interval, unit) = match rest.split_once(' ') {
+        Some(("other", unit)) => (2, unit),
+        Some((count, unit)) if count.parse::<u32>().is_ok() => (count.parse().ok()?, unit),
+        _ => (1, rest),
+    };
+
+    let frequency = match unit {
+        "day" | "days" => Some("DAILY"),
+        "week" | "weeks" => Some("WEEKLY"),
+        "month" | "months" => Some("MONTHLY"),
+        "year" | "years" => Some("YEARLY"),
+        _ => None,
+    };
+
+    let rule = match frequency {
+        Some(frequency) if interval > 1 => format!("FREQ={frequency};INTERVAL={interval}"),
+        Some(frequency) => format!("FREQ={frequency}"),
+        None if interval > 1 => return None,
+        None => match rest {
+            "weekday" | "workday" => String::from("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR"),
+            "weekend" => String::from("FREQ=WEEKLY;BYDAY=SA,SU"),
+            _ => by_day(rest).or_else(|| by_month_day(rest))?,
+        },
+    };
+
+    Some(rule)
+}
+
+/// A weekly rule for a list of weekdays, such as `mon, fri`.
+fn by_day(days: &str) -> Option<String> {
+    let days: Option<Vec<&str>> = days
+        .split([',', ' '])
+        .filter(|day| !day.is_empty() && *day != "and")
+        .map(|day| match day {
+            "mon" | "monday" => Some("MO"),
+            "tue" | "tuesday" => Some("TU"),
+            "wed" | "wednesday" => Some("WE"),
+            "thu" | "thursday" => Some("TH"),
+            "fri" | "friday" => Some("FR"),
+            "sat" | "saturday" => Some("SA"),
+            "sun" | "sunday" => Some("SU"),
+            _ => None,
+        })
+        .collect();
+
+    Some(format!("FREQ=WEEKLY;BYDAY={}", days?.join(",")))
+}
+
+/// A monthly rule for a day of the month, such as `15th`.
+fn by_month_day(day: &str) -> Option<String> {
+    let day = day
+        .strip_suffix("st")
+        .or_else(|| day.strip_suffix("nd"))
+        .or_else(|| day.strip_suffix("rd"))
+        .or_else(|| day.strip_suffix("th"))?;
+
+    match day.parse::<u8>() {
+        Ok(day @ 1..=31) => Some(format!("FREQ=MONTHLY;BYMONTHDAY={day}")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+use chrono::FixedOffset;
+
+#[cfg(test)]
+use state::{test_due, test_item};
+
+#[test]
+fn recurrence_rule_test() {
+    let rule = |string| recurrence_rule(string);
+
+    assert_eq!(rule("every day").as_deref(), Some("FREQ=DAILY"));
+    assert_eq!(rule("Every! day at 9am").as_deref(), Some("FREQ=DAILY"));
+    assert_eq!(
+        rule("every 3 days").as_deref(),
+        Some("FREQ=DAILY;INTERVAL=3")
+    );
+    assert_eq!(
+        rule("every other week").as_deref(),
+        Some("FREQ=WEEKLY;INTERVAL=2")
+    );
+    assert_eq!(rule("monthly").as_deref(), Some("FREQ=MONTHLY"));
+    assert_eq!(
+        rule("every mon, fri at 18:00").as_deref(),
+        Some("FREQ=WEEKLY;BYDAY=MO,FR")
+    );
+    assert_eq!(
+        rule("every weekday").as_deref(),
+        Some("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR")
+    );
+    assert_eq!(
+        rule("every 15th").as_deref(),
+        Some("FREQ=MONTHLY;BYMONTHDAY=15")
+    );
+
+    assert_eq!(rule("every 3rd friday"), None);
+    assert_eq!(rule("every 2 mondays"), None);
+    assert_eq!(rule("tomorrow"), None);
+}
+
+#[test]
+fn fold_line_test() {
+    let line = format!("SUMMARY:{}", "a".repeat(80));
+    let folded = fold_line(&line);
+    let lines: Vec<&str> = folded.split("\r\n").collect();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].len(), 75);
+    assert_eq!(lines[1], format!(" {}", "a".repeat(13)));
+
+    // Multi-byte characters are never split.
+    let folded = fold_line(&"é".repeat(40));
+    assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+}
+
+#[test]
+fn calendar_test() {
+    let mut due = test_due("2024-03-11T08:30:00Z");
+    due.timezone = Some(String::from("Europe/Berlin"));
+    due.string = String::from("every weekday at 9:30");
+    due.is_recurring = true;
+
+    let mut item = test_item("1", "Standup; sync, notes");
+    item.priority = 3;
+    item.due = Some(due);
+    item.labels = vec![String::from("work")];
+
+    let now = NaiveDate::from_ymd_opt(2024, 3, 10)
+        .and_then(|d| d.and_hms_opt(13, 0, 0))
+        .unwrap();
+    let clock = Clock::new(now, FixedOffset::east_opt(3600).unwrap());
+
+    let expected = [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//todr//todr//EN",
+        "CALSCALE:GREGORIAN",
+        "BEGIN:VTODO",
+        "UID:1@todr",
+        "DTSTAMP:20240310T120000Z",
+        "SUMMARY:Standup\\; sync\\, notes",
+        "DTSTART:20240311T083000Z",
+        "DUE:20240311T083000Z",
+        "PRIORITY:5",
+        "CATEGORIES:work",
+        "RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR",
+        "STATUS:NEEDS-ACTION",
+        "END:VTODO",
+        "END:VCALENDAR",
+        "",
+    ];
+    assert_eq!(calendar(&clock, &[&item], false), expected.join("\r\n"));
+
+    let events = calendar(&clock, &[&item], true);
+    assert!(events.contains("BEGIN:VEVENT\r\n"));
+    assert!(events.contains("DTSTART:20240311T083000Z\r\n"));
+    assert!(!events.contains("DUE"));
+    assert!(!events.contains("STATUS"));
+}
+
+#[test]
+fn calendar_recurring_todo_test() {
+    let item = |date: &str, string: &str, is_recurring: bool| {
+        let mut due = test_due(date);
+        due.string = String::from(string);
+        due.is_recurring = is_recurring;
+
+        let mut item = test_item("1", "Water plants");
+        item.due = Some(due);
+        item
+    };
+    let clock = Clock::new(
+        NaiveDate::from_ymd_opt(2024, 3, 10)
+            .and_then(|d| d.and_hms_opt(13, 0, 0))
+            .unwrap(),
+        FixedOffset::east_opt(0).unwrap(),
+    );
+
+    // To-dos with a recurrence rule start on the day they are due.
+    let recurring = calendar(&clock, &[&item("2024-03-11", "every monday", true)], false);
+    assert!(recurring.contains("DTSTART;VALUE=DATE:20240311\r\nDUE;VALUE=DATE:20240311\r\n"));
+    assert!(recurring.contains("RRULE:FREQ=WEEKLY;BYDAY=MO\r\n"));
+
+    // Without a rule the start is left out, as before.
+    let unknown = calendar(
+        &clock,
+        &[&item("2024-03-11", "every 3rd monday", true)],
+        false,
+    );
+    assert!(!unknown.contains("DTSTART"));
+    assert!(!unknown.contains("RRULE"));
+
+    let once = calendar(&clock, &[&item("2024-03-11", "monday", false)], false);
+    assert!(!once.contains("DTSTART"));
+}
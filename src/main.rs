@@ -19,22 +19,52 @@
 // Disable unsafe code.
 #![forbid(unsafe_code)]
 
+extern crate chrono;
 extern crate dirs;
 extern crate reqwest;
 extern crate rustyline;
 extern crate serde;
+#[macro_use]
 extern crate serde_json;
 extern crate term;
+extern crate toml;
+extern crate uuid;
 
 // Use the module in the sub directory.
+mod cache;
+mod cli;
 mod completer;
 mod config;
+mod dates;
+mod error;
+mod export;
 mod handlers;
+mod ical;
+mod output;
+mod query;
 mod renderer;
 mod repl;
+mod state;
+mod todotxt;
 mod types;
 
+use std::env;
+use std::process;
+
 fn main() {
-    let mut repl = repl::Todr::new();
-    repl.process_command_loop();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Err(e) = config::Configuration::init() {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+
+    // Without arguments we start the interactive REPL,
+    // otherwise the given command is run once.
+    if args.is_empty() {
+        let mut repl = repl::Todr::new();
+        repl.process_command_loop();
+    } else {
+        process::exit(cli::run(&args));
+    }
 }
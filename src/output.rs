@@ -0,0 +1,231 @@
+//! This module implements the machine readable output of the listings.
+//!
+//! Each listed resource is printed with all the fields the API gave us,
+//! plus the names of the resources it refers to and its parsed dates,
+//! so scripts don't have to resolve them. The added fields are documented
+//! in the README, and fields are only ever added to them, never removed.
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json;
+
+use dates::{Clock, Due};
+use error::TodrError;
+use state::{State, TreePosition};
+use types;
+
+/// How listings are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Colored text for humans.
+    #[default]
+    Text,
+
+    /// A single JSON array.
+    Json,
+
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl OutputFormat {
+    pub fn parse(format: &str) -> Result<Self, TodrError> {
+        match format {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::JsonLines),
+            _ => Err(TodrError::Usage(format!(
+                "Unknown format, expected text, json or jsonl: {format}"
+            ))),
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+            Self::JsonLines => "jsonl",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ItemOutput<'a> {
+    #[serde(flatten)]
+    item: &'a types::ItemStruct,
+
+    /// The name of the project the item is in.
+    project_name: Option<&'a str>,
+
+    /// The name of the section the item is in.
+    section_name: Option<&'a str>,
+
+    /// The depth of the item below its top-level ancestor.
+    depth: usize,
+
+    /// The day the item is due on, as `YYYY-MM-DD`.
+    due_date: Option<String>,
+
+    /// The time the item is due at in RFC 3339, null for all-day items.
+    due_datetime: Option<String>,
+
+    /// Whether the due date has passed.
+    is_overdue: bool,
+
+    /// The time the item was added at in RFC 3339.
+    added_datetime: Option<String>,
+
+    /// The time the item was completed at in RFC 3339.
+    completed_datetime: Option<String>,
+
+    /// The number of comments on the item.
+    note_count: usize,
+}
+
+impl<'a> ItemOutput<'a> {
+    pub fn new(
+        state: &'a State,
+        clock: &Clock,
+        item: &'a types::ItemStruct,
+        position: &TreePosition,
+    ) -> Self {
+        let project_name = state.project(&item.project_id).map(|p| p.name.as_str());
+        let section_name = item
+            .section_id
+            .as_ref()
+            .and_then(|id| state.section(id))
+            .map(|s| s.name.as_str());
+
+        let due = clock.due(item);
+        let due_datetime = match due {
+            Some(Due::At(at)) => clock.rfc3339(at),
+            _ => None,
+        };
+
+        let timestamp = |timestamp: Option<&String>| clock.rfc3339(clock.timestamp(timestamp?)?);
+
+        Self {
+            item,
+            project_name,
+            section_name,
+            depth: position.last.len().saturating_sub(1),
+            due_date: due.map(|due| due.date().format("%Y-%m-%d").to_string()),
+            due_datetime,
+            is_overdue: due.is_some_and(|due| clock.is_overdue(due)),
+            added_datetime: timestamp(item.added_at.as_ref()),
+            completed_datetime: timestamp(item.completed_at.as_ref()),
+            note_count: state.notes_on_item(&item.id).len(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ProjectOutput<'a> {
+    #[serde(flatten)]
+    project: &'a types::ProjectStruct,
+
+    /// The name of the parent project.
+    parent_name: Option<&'a str>,
+
+    /// The depth of the project below its top-level ancestor.
+    depth: usize,
+}
+
+impl<'a> ProjectOutput<'a> {
+    pub fn new(
+        state: &'a State,
+        project: &'a types::ProjectStruct,
+        position: &TreePosition,
+    ) -> Self {
+        let parent_name = project
+            .parent_id
+            .as_ref()
+            .and_then(|id| state.project(id))
+            .map(|p| p.name.as_str());
+
+        Self {
+            project,
+            parent_name,
+            depth: position.last.len().saturating_sub(1),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReminderOutput<'a> {
+    #[serde(flatten)]
+    reminder: &'a types::ReminderStruct,
+
+    /// The content of the item the reminder is for.
+    item_content: &'a str,
+
+    /// The time the reminder goes off at in RFC 3339.
+    remind_datetime: Option<String>,
+}
+
+impl<'a> ReminderOutput<'a> {
+    pub fn new(
+        clock: &Clock,
+        reminder: &'a types::ReminderStruct,
+        item: &'a types::ItemStruct,
+        at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            reminder,
+            item_content: &item.content,
+            remind_datetime: clock.rfc3339(at),
+        }
+    }
+}
+
+/// Print the listed values in the given format.
+pub fn print<T: Serialize>(format: OutputFormat, values: &[T]) -> Result<(), TodrError> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(values)?),
+        OutputFormat::JsonLines => {
+            for value in values {
+                println!("{}", serde_json::to_string(value)?);
+            }
+        }
+        OutputFormat::Text => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+use chrono::{FixedOffset, NaiveDate};
+#[cfg(test)]
+use state::{test_due, test_item};
+
+#[test]
+fn item_output_test() {
+    let mut due = test_due("2024-03-09T18:00:00Z");
+    due.timezone = Some(String::from("Europe/Berlin"));
+    due.string = String::from("tomorrow 7pm");
+
+    let mut item = test_item("1", "Buy milk");
+    item.priority = 4;
+    item.due = Some(due);
+    item.added_at = Some(String::from("2024-03-01T10:00:00.000000Z"));
+
+    let now = NaiveDate::from_ymd_opt(2024, 3, 10)
+        .and_then(|d| d.and_hms_opt(12, 0, 0))
+        .unwrap();
+    let clock = Clock::new(now, FixedOffset::east_opt(3600).unwrap());
+
+    let state = State::new();
+    let output = ItemOutput::new(&state, &clock, &item, &TreePosition::default());
+    let value = serde_json::to_value(output).unwrap();
+
+    // The fields of the API are kept as they are.
+    assert_eq!(value["content"], "Buy milk");
+    assert_eq!(value["due"]["string"], "tomorrow 7pm");
+
+    assert_eq!(value["project_name"], serde_json::Value::Null);
+    assert_eq!(value["depth"], 0);
+    assert_eq!(value["due_date"], "2024-03-09");
+    assert_eq!(value["due_datetime"], "2024-03-09T19:00:00+01:00");
+    assert_eq!(value["is_overdue"], true);
+    assert_eq!(value["added_datetime"], "2024-03-01T11:00:00+01:00");
+}
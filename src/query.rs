@@ -0,0 +1,624 @@
+//! This module implements a local evaluator for todoist filter queries.
+//!
+//! Queries such as `(today | overdue) & p1 & @work & #Project` are parsed
+//! into an expression tree, which is then evaluated against the synced
+//! items, so filters work offline and without a round trip to the server.
+//!
+//! Link: <https://todoist.com/help/articles/introduction-to-filters>
+
+use chrono::{Duration, NaiveDate};
+use std::fmt;
+
+use dates::{Clock, Due};
+use state::State;
+use types;
+
+// The most days `next N days` may look ahead, later dates can't be
+// represented by chrono.
+static MAX_DAYS: i64 = 36500;
+
+/// Failures to parse a query, usually syntax we don't support.
+#[derive(Debug, PartialEq, Eq)]
+pub struct QueryError(String);
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid query: {}", self.0)
+    }
+}
+
+/// A date as it appears in a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateSpec {
+    /// A number of days relative to today.
+    Relative(i64),
+
+    /// A fixed date.
+    Fixed(NaiveDate),
+}
+
+/// Who a task is assigned to, or by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Person {
+    Me,
+    Others,
+}
+
+/// A single filter, the leaves of the expression tree.
+#[derive(Debug, PartialEq, Eq)]
+enum Term {
+    /// Every task.
+    All,
+    /// `p1` to `p4`, stored with the API value where 4 is `p1`.
+    Priority(u8),
+    /// `@name`, may contain `*` wildcards.
+    Label(String),
+    /// `no labels`.
+    NoLabels,
+    /// `#name`, may contain `*` wildcards.
+    Project(String),
+    /// `##name`, the project along with its sub-projects.
+    ProjectTree(String),
+    /// `today`, `tomorrow`, `date: 2024-01-31`.
+    Due(DateSpec),
+    /// `due before: <date>`.
+    DueBefore(DateSpec),
+    /// `due after: <date>`.
+    DueAfter(DateSpec),
+    /// `overdue`.
+    Overdue,
+    /// `created: <date>`.
+    Created(DateSpec),
+    /// `created before: <date>`.
+    CreatedBefore(DateSpec),
+    /// `created after: <date>`.
+    CreatedAfter(DateSpec),
+    /// `7 days` or `next 7 days`, the given number of days from today.
+    NextDays(i64),
+    /// `no date`.
+    NoDate,
+    /// `recurring`.
+    Recurring,
+    /// `assigned to: me` or `assigned to: others`.
+    AssignedTo(Person),
+    /// `assigned by: me` or `assigned by: others`.
+    AssignedBy(Person),
+    /// `assigned`, tasks assigned to anyone.
+    Assigned,
+    /// `search: text`.
+    Search(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Expr {
+    Term(Term),
+    Not(Box<Self>),
+    And(Box<Self>, Box<Self>),
+    Or(Box<Self>, Box<Self>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Term(String),
+    And,
+    Or,
+    Not,
+    Comma,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Term(text) => write!(f, "`{text}`"),
+            Self::And => write!(f, "`&`"),
+            Self::Or => write!(f, "`|`"),
+            Self::Not => write!(f, "`!`"),
+            Self::Comma => write!(f, "`,`"),
+            Self::Open => write!(f, "`(`"),
+            Self::Close => write!(f, "`)`"),
+        }
+    }
+}
+
+/// A parsed query.
+///
+/// Queries separated by commas are shown as separate lists by todoist,
+/// here an item matches if it matches any of them.
+#[derive(Debug)]
+pub struct Query {
+    lists: Vec<Expr>,
+}
+
+/// What the terms of a query are evaluated against.
+pub struct Context<'a> {
+    /// The state the items belong to, used to resolve projects and the user.
+    state: &'a State,
+
+    /// The clock of the user, which relative dates are resolved with.
+    clock: Clock,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(state: &'a State) -> Self {
+        Self {
+            state,
+            clock: Clock::for_user(state.user.as_ref()),
+        }
+    }
+}
+
+impl Query {
+    /// Parse a query, failing on syntax we can't evaluate locally.
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+
+        let mut lists = vec![parser.parse_or()?];
+        while parser.next_if(&Token::Comma) {
+            lists.push(parser.parse_or()?);
+        }
+
+        if let Some(token) = parser.peek() {
+            return Err(QueryError(format!("Unexpected {token}")));
+        }
+
+        Ok(Self { lists })
+    }
+
+    /// Whether the item matches the query.
+    pub fn matches(&self, item: &types::ItemStruct, context: &Context) -> bool {
+        self.lists.iter().any(|expr| evaluate(expr, item, context))
+    }
+}
+
+/// Split a query into its operators and terms.
+///
+/// Terms run until the next operator, so they may contain spaces, as in
+/// `no date` or `#My Project`. Operators can be escaped with a backslash.
+fn tokenize(query: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut term = String::new();
+    let mut chars = query.chars();
+
+    let finish_term = |term: &mut String, tokens: &mut Vec<Token>| {
+        let text = term.trim();
+        if !text.is_empty() {
+            tokens.push(Token::Term(String::from(text)));
+        }
+        term.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '&' => Token::And,
+            '|' => Token::Or,
+            '!' if term.trim().is_empty() => Token::Not,
+            ',' => Token::Comma,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '\\' => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| QueryError(String::from("Trailing backslash")))?;
+                term.push(escaped);
+                continue;
+            }
+            _ => {
+                term.push(c);
+                continue;
+            }
+        };
+
+        finish_term(&mut term, &mut tokens);
+        tokens.push(token);
+    }
+
+    finish_term(&mut term, &mut tokens);
+    Ok(tokens)
+}
+
+/// A recursive descent parser over the tokens of a query.
+///
+/// `!` binds tighter than `&`, which binds tighter than `|`.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next_if(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_and()?;
+        while self.next_if(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.parse_unary()?;
+        while self.next_if(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, QueryError> {
+        let token = self.peek();
+        self.position += 1;
+
+        match token {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let expr = self.parse_or()?;
+                if self.next_if(&Token::Close) {
+                    Ok(expr)
+                } else {
+                    Err(QueryError(String::from("Missing closing parenthesis")))
+                }
+            }
+            Some(Token::Term(text)) => Ok(Expr::Term(parse_term(text)?)),
+            Some(token) => Err(QueryError(format!("Unexpected {token}"))),
+            None => Err(QueryError(String::from("Unexpected end of query"))),
+        }
+    }
+}
+
+fn parse_term(text: &str) -> Result<Term, QueryError> {
+    let lower = text.to_lowercase();
+
+    if let Some(name) = text.strip_prefix("##") {
+        return Ok(Term::ProjectTree(name.trim().to_lowercase()));
+    }
+
+    if let Some(name) = text.strip_prefix('#') {
+        return Ok(Term::Project(name.trim().to_lowercase()));
+    }
+
+    if let Some(name) = text.strip_prefix('@') {
+        return Ok(Term::Label(name.trim().to_lowercase()));
+    }
+
+    if let Some((key, value)) = lower.split_once(':') {
+        let value = value.trim();
+        return match key.trim() {
+            "date" | "due" => Ok(Term::Due(parse_date(value)?)),
+            "due before" | "date before" => Ok(Term::DueBefore(parse_date(value)?)),
+            "due after" | "date after" => Ok(Term::DueAfter(parse_date(value)?)),
+            "created" => Ok(Term::Created(parse_date(value)?)),
+            "created before" => Ok(Term::CreatedBefore(parse_date(value)?)),
+            "created after" => Ok(Term::CreatedAfter(parse_date(value)?)),
+            "assigned to" => Ok(Term::AssignedTo(parse_person(value)?)),
+            "assigned by" => Ok(Term::AssignedBy(parse_person(value)?)),
+            "search" => Ok(Term::Search(String::from(value))),
+            _ => Err(QueryError(format!("Unsupported filter: {text}"))),
+        };
+    }
+
+    match lower.as_str() {
+        "all" | "view all" => return Ok(Term::All),
+        "p1" => return Ok(Term::Priority(4)),
+        "p2" => return Ok(Term::Priority(3)),
+        "p3" => return Ok(Term::Priority(2)),
+        "p4" => return Ok(Term::Priority(1)),
+        "overdue" | "od" => return Ok(Term::Overdue),
+        "no date" | "no due date" => return Ok(Term::NoDate),
+        "no labels" => return Ok(Term::NoLabels),
+        "recurring" => return Ok(Term::Recurring),
+        "assigned" => return Ok(Term::Assigned),
+        _ => {}
+    }
+
+    let days = lower.strip_prefix("next ").unwrap_or(&lower);
+    if let Some(count) = days
+        .strip_suffix(" days")
+        .or_else(|| days.strip_suffix(" day"))
+    {
+        if let Ok(count) = count.trim().parse::<i64>() {
+            if !(0..=MAX_DAYS).contains(&count) {
+                return Err(QueryError(format!(
+                    "Unsupported number of days, use up to {MAX_DAYS}: {text}"
+                )));
+            }
+
+            return Ok(Term::NextDays(count));
+        }
+    }
+
+    parse_date(&lower)
+        .map(Term::Due)
+        .map_err(|_| QueryError(format!("Unsupported filter: {text}")))
+}
+
+fn parse_date(text: &str) -> Result<DateSpec, QueryError> {
+    match text {
+        "today" | "tod" => Ok(DateSpec::Relative(0)),
+        "tomorrow" | "tom" => Ok(DateSpec::Relative(1)),
+        "yesterday" => Ok(DateSpec::Relative(-1)),
+        _ => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .map(DateSpec::Fixed)
+            .map_err(|_| QueryError(format!("Unsupported date: {text}"))),
+    }
+}
+
+fn parse_person(text: &str) -> Result<Person, QueryError> {
+    match text {
+        "me" => Ok(Person::Me),
+        "others" => Ok(Person::Others),
+        _ => Err(QueryError(format!(
+            "Unsupported assignee, use `me` or `others`: {text}"
+        ))),
+    }
+}
+
+/// Match text against a pattern where `*` matches any run of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // Without a wildcard the text has to match exactly.
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+impl DateSpec {
+    fn resolve(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Relative(days) => today + Duration::days(days),
+            Self::Fixed(date) => date,
+        }
+    }
+}
+
+/// The ids of the projects matching the pattern, and their sub-projects
+/// if requested.
+fn matching_projects<'a>(state: &'a State, pattern: &str, descendants: bool) -> Vec<&'a str> {
+    let mut ids: Vec<&str> = state
+        .projects
+        .iter()
+        .filter(|p| wildcard_match(pattern, &p.name.to_lowercase()))
+        .map(|p| p.id.as_str())
+        .collect();
+
+    if descendants {
+        // Walk down the hierarchy until no more children are found.
+        let mut index = 0;
+        while index < ids.len() {
+            let parent_id = ids[index];
+            for project in &state.projects {
+                if project.parent_id.as_deref() == Some(parent_id)
+                    && !ids.contains(&project.id.as_str())
+                {
+                    ids.push(&project.id);
+                }
+            }
+            index += 1;
+        }
+    }
+
+    ids
+}
+
+fn is_me(uid: Option<&str>, context: &Context) -> bool {
+    let user_id = context.state.user.as_ref().map(|u| u.id.as_str());
+    uid.is_some() && uid == user_id
+}
+
+fn evaluate(expr: &Expr, item: &types::ItemStruct, context: &Context) -> bool {
+    match expr {
+        Expr::Term(term) => evaluate_term(term, item, context),
+        Expr::Not(expr) => !evaluate(expr, item, context),
+        Expr::And(lhs, rhs) => evaluate(lhs, item, context) && evaluate(rhs, item, context),
+        Expr::Or(lhs, rhs) => evaluate(lhs, item, context) || evaluate(rhs, item, context),
+    }
+}
+
+fn evaluate_term(term: &Term, item: &types::ItemStruct, context: &Context) -> bool {
+    let clock = &context.clock;
+    let today = clock.today();
+    let due = clock.due(item).map(Due::date);
+    let created = item
+        .added_at
+        .as_deref()
+        .and_then(|added_at| clock.timestamp(added_at))
+        .map(|added_at| added_at.date());
+
+    match term {
+        Term::All => true,
+        Term::Priority(priority) => item.priority == *priority,
+        Term::Label(pattern) => item
+            .labels
+            .iter()
+            .any(|label| wildcard_match(pattern, &label.to_lowercase())),
+        Term::NoLabels => item.labels.is_empty(),
+        Term::Project(pattern) => {
+            matching_projects(context.state, pattern, false).contains(&item.project_id.as_str())
+        }
+        Term::ProjectTree(pattern) => {
+            matching_projects(context.state, pattern, true).contains(&item.project_id.as_str())
+        }
+        Term::Due(date) => due == Some(date.resolve(today)),
+        Term::DueBefore(date) => due.is_some_and(|due| due < date.resolve(today)),
+        Term::DueAfter(date) => due.is_some_and(|due| due > date.resolve(today)),
+        Term::Overdue => clock.due(item).is_some_and(|due| clock.is_overdue(due)),
+        Term::Created(date) => created == Some(date.resolve(today)),
+        Term::CreatedBefore(date) => created.is_some_and(|created| created < date.resolve(today)),
+        Term::CreatedAfter(date) => created.is_some_and(|created| created > date.resolve(today)),
+        Term::NextDays(days) => {
+            due.is_some_and(|due| due >= today && due < today + Duration::days(*days))
+        }
+        Term::NoDate => due.is_none(),
+        Term::Recurring => item.due.as_ref().is_some_and(|due| due.is_recurring),
+        Term::AssignedTo(Person::Me) => is_me(item.responsible_uid.as_deref(), context),
+        Term::AssignedTo(Person::Others) => {
+            item.responsible_uid.is_some() && !is_me(item.responsible_uid.as_deref(), context)
+        }
+        Term::AssignedBy(Person::Me) => is_me(item.assigned_by_uid.as_deref(), context),
+        Term::AssignedBy(Person::Others) => {
+            item.assigned_by_uid.is_some() && !is_me(item.assigned_by_uid.as_deref(), context)
+        }
+        Term::Assigned => item.responsible_uid.is_some(),
+        Term::Search(text) => item.content.to_lowercase().contains(text.as_str()),
+    }
+}
+
+#[cfg(test)]
+use chrono::FixedOffset;
+#[cfg(test)]
+use serde_json;
+#[cfg(test)]
+use state::{test_due, test_item};
+
+#[cfg(test)]
+fn test_state() -> State {
+    let item = |id: &str, project_id: &str, priority: u8, labels: &[&str], due: Option<&str>| {
+        let mut item = test_item(id, &format!("Task {id}"));
+        item.project_id = String::from(project_id);
+        item.priority = priority;
+        item.labels = labels.iter().map(|l| String::from(*l)).collect();
+        item.due = due.map(test_due);
+        item.added_at = Some(format!("2024-03-0{id}T10:00:00.000000Z"));
+        item
+    };
+    let project = |id: &str, name: &str, parent_id: &str| {
+        let json = format!(
+            r#"{{
+              "id": "{id}", "name": "{name}", "color": "red", "parent_id": {parent_id},
+              "child_order": 1, "collapsed": false, "shared": false,
+              "can_assign_tasks": false, "is_deleted": false, "is_archived": false,
+              "is_favorite": false, "sync_id": null, "inbox_project": false,
+              "view_style": "list"
+            }}"#
+        );
+        serde_json::from_str(&json).unwrap()
+    };
+
+    let mut state = State::new();
+    state.projects = vec![
+        project("10", "Work", "null"),
+        project("11", "Meetings", r#""10""#),
+        project("12", "Home", "null"),
+    ];
+    state.items = vec![
+        item("1", "10", 4, &["work"], Some("2024-03-10")),
+        item("2", "11", 1, &["work", "calls"], Some("2024-03-12")),
+        item("3", "12", 3, &[], None),
+        item("4", "12", 4, &["errands"], Some("2024-03-09T18:00:00")),
+    ];
+    state
+}
+
+#[cfg(test)]
+fn matching_ids(state: &State, query: &str) -> Vec<String> {
+    let query = Query::parse(query).unwrap();
+    let now = NaiveDate::from_ymd_opt(2024, 3, 10)
+        .and_then(|d| d.and_hms_opt(12, 0, 0))
+        .unwrap();
+    let context = Context {
+        state,
+        clock: Clock::new(now, FixedOffset::east_opt(0).unwrap()),
+    };
+
+    state
+        .items
+        .iter()
+        .filter(|i| query.matches(i, &context))
+        .map(|i| i.id.clone())
+        .collect()
+}
+
+#[test]
+fn parse_query_test() {
+    assert_eq!(
+        tokenize("(today | overdue) & p1").unwrap(),
+        vec![
+            Token::Open,
+            Token::Term(String::from("today")),
+            Token::Or,
+            Token::Term(String::from("overdue")),
+            Token::Close,
+            Token::And,
+            Token::Term(String::from("p1")),
+        ]
+    );
+
+    // Escaped operators are part of the term.
+    assert_eq!(
+        tokenize(r"#Work \& Play").unwrap(),
+        vec![Token::Term(String::from("#Work & Play"))]
+    );
+
+    assert!(Query::parse("(today | overdue").is_err());
+    assert!(Query::parse("today &").is_err());
+    assert!(Query::parse("shared").is_err());
+
+    // Counts of days which can't be resolved to a date.
+    assert!(Query::parse("next 365 days").is_ok());
+    assert!(Query::parse("1000000000 days").is_err());
+    assert!(Query::parse("-3 days").is_err());
+}
+
+#[test]
+fn evaluate_query_test() {
+    let state = test_state();
+
+    assert_eq!(matching_ids(&state, "p1"), vec!["1", "4"]);
+    assert_eq!(matching_ids(&state, "@work & !@calls"), vec!["1"]);
+    assert_eq!(matching_ids(&state, "@*s"), vec!["2", "4"]);
+    assert_eq!(matching_ids(&state, "#work"), vec!["1"]);
+    assert_eq!(matching_ids(&state, "##Work"), vec!["1", "2"]);
+    assert_eq!(matching_ids(&state, "no date"), vec!["3"]);
+    assert_eq!(
+        matching_ids(&state, "(today | overdue) & p1"),
+        vec!["1", "4"]
+    );
+    assert_eq!(matching_ids(&state, "3 days"), vec!["1", "2"]);
+    assert_eq!(matching_ids(&state, "due before: 2024-03-10"), vec!["4"]);
+    assert_eq!(matching_ids(&state, "#Home, @calls"), vec!["2", "3", "4"]);
+    assert_eq!(matching_ids(&state, "search: task 3"), vec!["3"]);
+    assert_eq!(
+        matching_ids(&state, "created after: 2024-03-02"),
+        vec!["3", "4"]
+    );
+}
+
+#[test]
+fn wildcard_match_test() {
+    assert!(wildcard_match("work", "work"));
+    assert!(!wildcard_match("work", "workout"));
+    assert!(wildcard_match("work*", "workout"));
+    assert!(wildcard_match("*out", "workout"));
+    assert!(wildcard_match("w*k*t", "workout"));
+    assert!(!wildcard_match("w*z", "workout"));
+}
@@ -1,97 +1,357 @@
-use term;
 ///!
 ///! Defines methods for rendering output to the console.
 ///!
 ///! The goal being that we separate text formatting
 ///! from the actual processing of data exposed by the API.
 ///!
+use std::io;
+use std::io::IsTerminal;
+use term;
+
 // Use our internal types module.
+use config::Configuration;
+use dates::Clock;
+use error::TodrError;
+use state::TreePosition;
 use types;
 
-fn project_color_id_map(color: u8) -> term::color::Color {
-    // The color of the project (a number between 0 and 11, or between 0 and 21 for premium users).
+fn color_id_map(color: &str) -> term::color::Color {
+    // The color of the project, by the name the API uses for it.
     match color {
-        // Normal Colors
-
-        // #95ef63
-        0 => term::color::BRIGHT_GREEN,
-        // #ff8581 - A pinkish, red.
-        1 => term::color::BRIGHT_RED,
-        // #ffc471 - Orange
-        2 => term::color::RED,
-        // #f9ec75
-        3 => term::color::YELLOW,
-        // #a8c8e4 - A light blue.
-        4 => term::color::BRIGHT_BLUE,
-        // #d2b8a3 - A tan color
-        5 => term::color::WHITE,
-        // #e2a8e4 - Another pink.
-        6 => term::color::BRIGHT_RED,
-        // #cccccc - A gray, map to white
-        7 => term::color::WHITE,
-        // #fb886e - Orange, map to red
-        8 => term::color::RED,
-        // #ffcc00
-        9 => term::color::YELLOW,
-        // #74e8d3 - bright green
-        10 => term::color::BRIGHT_GREEN,
-        // #3bd5fb
-        11 => term::color::BRIGHT_BLUE,
-
-        // Premium Colors
-
-        // #dc4fad - pinkish
-        12 => term::color::BRIGHT_RED,
-        // #ac193d - dark red
-        13 => term::color::MAGENTA,
-        // #d24726 - normal red
-        14 => term::color::RED,
-        // #82ba00 - medium green
-        15 => term::color::GREEN,
-        // #03b3b2
-        16 => term::color::BRIGHT_CYAN,
-        // #008299
-        17 => term::color::CYAN,
-        // #5db2ff
-        18 => term::color::BRIGHT_BLUE,
-        // #0072c6
-        19 => term::color::BLUE,
-        // #000000
-        20 => term::color::BLACK,
-        // #777777 - Gray, we don't have default to white.
-        21 => term::color::WHITE,
-
-        // Default handler.
-        _ => unreachable!("Unknown color id! {}", color),
+        // #b8256f - A dark pink.
+        "berry_red" => term::color::MAGENTA,
+        // #db4035
+        "red" => term::color::BRIGHT_RED,
+        // #ff9933 - Orange, map to red
+        "orange" => term::color::RED,
+        // #fad000
+        "yellow" => term::color::YELLOW,
+        // #afb83b - A yellowish green.
+        "olive_green" => term::color::GREEN,
+        // #7ecc49
+        "lime_green" => term::color::BRIGHT_GREEN,
+        // #299438
+        "green" => term::color::GREEN,
+        // #6accbc
+        "mint_green" => term::color::BRIGHT_CYAN,
+        // #158fad
+        "teal" => term::color::CYAN,
+        // #14aaf5
+        "sky_blue" => term::color::BRIGHT_BLUE,
+        // #96c3eb - A light blue.
+        "light_blue" => term::color::BRIGHT_BLUE,
+        // #4073ff
+        "blue" => term::color::BLUE,
+        // #884dff - A purple.
+        "grape" => term::color::MAGENTA,
+        // #af38eb
+        "violet" => term::color::BRIGHT_MAGENTA,
+        // #eb96eb - A light purple.
+        "lavender" => term::color::BRIGHT_MAGENTA,
+        // #e05194 - pinkish
+        "magenta" => term::color::BRIGHT_RED,
+        // #ff8d85 - A pinkish, red.
+        "salmon" => term::color::BRIGHT_RED,
+        // #808080 - A dark gray.
+        "charcoal" => term::color::BRIGHT_BLACK,
+        // #b8b8b8 - A gray, map to white
+        "grey" => term::color::WHITE,
+        // #ccac93 - A tan color
+        "taupe" => term::color::WHITE,
+
+        // Colors added to the API after this was written.
+        _ => term::color::WHITE,
+    }
+}
+
+/// The color of an item, by the priority the API uses for it.
+const fn priority_color(priority: u8) -> Option<term::color::Color> {
+    match priority {
+        // p1
+        4 => Some(term::color::RED),
+        // p2 - Orange, the closest terminal color is yellow.
+        3 => Some(term::color::YELLOW),
+        // p3
+        2 => Some(term::color::BLUE),
+        // p4 - The natural priority isn't highlighted.
+        _ => None,
+    }
+}
+
+/// Print a line in the given color.
+///
+/// Colors are skipped when they are disabled, or when stdout isn't a
+/// terminal which supports them, for example when output is piped.
+fn print_colored(line: &str, color: term::color::Color) -> Result<(), TodrError> {
+    print_segments(&[(String::from(line), Some(color))])
+}
+
+/// Print a line made up of segments, each in its own color, if any.
+///
+/// Colors are skipped in the same cases as for `print_colored`.
+fn print_segments(segments: &[(String, Option<term::color::Color>)]) -> Result<(), TodrError> {
+    let terminal = if Configuration::color() && io::stdout().is_terminal() {
+        term::stdout().filter(|t| t.supports_color())
+    } else {
+        None
+    };
+
+    let Some(mut terminal) = terminal else {
+        let line: String = segments.iter().map(|(text, _)| text.as_str()).collect();
+        println!("{line}");
+        return Ok(());
+    };
+
+    for (text, color) in segments {
+        match color {
+            Some(color) => {
+                terminal.fg(*color)?;
+                print!("{text}");
+                terminal.reset()?;
+            }
+            None => print!("{text}"),
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Print the heading of a group of items, in bold when supported.
+pub fn render_heading(heading: &str) -> Result<(), TodrError> {
+    print_heading(heading, None)
+}
+
+/// Print the name of a project above its items, in the color it has set.
+pub fn render_project_heading(project: &types::ProjectStruct) -> Result<(), TodrError> {
+    print_heading(&project.name, Some(color_id_map(&project.color)))
+}
+
+fn print_heading(heading: &str, color: Option<term::color::Color>) -> Result<(), TodrError> {
+    let terminal = if Configuration::color() && io::stdout().is_terminal() {
+        term::stdout().filter(|t| t.supports_attr(term::Attr::Bold))
+    } else {
+        None
+    };
+
+    println!();
+    let Some(mut terminal) = terminal else {
+        println!("{heading}");
+        return Ok(());
+    };
+
+    terminal.attr(term::Attr::Bold)?;
+    if let Some(color) = color {
+        terminal.fg(color)?;
     }
+    println!("{heading}");
+    terminal.reset()?;
+
+    Ok(())
+}
+
+/// The connectors drawing the branches of a tree.
+struct TreeGlyphs {
+    branch: &'static str,
+    last_branch: &'static str,
+    trunk: &'static str,
+    space: &'static str,
+}
+
+static UNICODE_TREE: TreeGlyphs = TreeGlyphs {
+    branch: "├─ ",
+    last_branch: "└─ ",
+    trunk: "│  ",
+    space: "   ",
+};
+
+static ASCII_TREE: TreeGlyphs = TreeGlyphs {
+    branch: "|- ",
+    last_branch: "`- ",
+    trunk: "|  ",
+    space: "   ",
+};
+
+/// Draw the branches leading up to a resource in a tree.
+///
+/// Top-level resources have no branches, their children are connected
+/// to them with box-drawing glyphs, or ASCII where those aren't supported.
+fn tree_prefix(position: &TreePosition) -> String {
+    let glyphs = if Configuration::ascii_tree() {
+        &ASCII_TREE
+    } else {
+        &UNICODE_TREE
+    };
+
+    draw_tree_prefix(position, glyphs)
 }
 
-pub fn render_project(project: &types::ProjectStruct) {
-    // Switch the term to the color the project has set.
-    let mut terminal = term::stdout().expect("Failed to obtain stdout!");
-    let color = project_color_id_map(project.color);
+fn draw_tree_prefix(position: &TreePosition, glyphs: &TreeGlyphs) -> String {
+    let mut prefix = String::new();
+    let Some((last, ancestors)) = position.last.split_last() else {
+        return prefix;
+    };
 
-    // Double the indention level so we have a nice visual indent.
-    let ident = usize::from(project.indent * 2);
+    // The top-level has no branches, so its flag is skipped.
+    for ancestor_last in ancestors.iter().skip(1) {
+        prefix.push_str(if *ancestor_last {
+            glyphs.space
+        } else {
+            glyphs.trunk
+        });
+    }
+
+    if !ancestors.is_empty() {
+        prefix.push_str(if *last {
+            glyphs.last_branch
+        } else {
+            glyphs.branch
+        });
+    }
+
+    prefix
+}
+
+pub fn render_project(
+    project: &types::ProjectStruct,
+    position: &TreePosition,
+) -> Result<(), TodrError> {
+    // Show the project in the color it has set.
+    let color = color_id_map(&project.color);
 
-    terminal
-        .fg(color)
-        .expect("Failed to set console foreground");
+    print_segments(&[
+        (format!("  {}", tree_prefix(position)), None),
+        (project.name.clone(), Some(color)),
+    ])
+}
 
-    println!("{:ident$}{}", "", project.name, ident = ident);
+pub fn render_filter(filter: &types::FilterStruct) -> Result<(), TodrError> {
+    let line = format!("  {} ({})", filter.name, filter.query);
+    print_colored(&line, color_id_map(&filter.color))
+}
 
-    terminal.reset().expect("Failed to reset termianl color");
+pub fn render_label(label: &types::LabelStruct) -> Result<(), TodrError> {
+    print_colored(&format!("  @{}", label.name), color_id_map(&label.color))
 }
 
-pub fn render_item(item: &types::ItemStruct) {
-    // Double the indention level so we have a nice visual indent.
-    let ident = usize::from(item.indent * 2);
+pub fn render_item(
+    item: &types::ItemStruct,
+    position: &TreePosition,
+    labels: &[types::LabelStruct],
+    project: Option<&types::ProjectStruct>,
+    notes: usize,
+    clock: &Clock,
+) -> Result<(), TodrError> {
+    // Prioritized items are marked in the margin left of the tree,
+    // others are padded to line up with them.
+    let color = priority_color(item.priority);
+    let marker = Configuration::priority_marker();
+    let marker = if color.is_some() {
+        marker
+    } else {
+        " ".repeat(marker.chars().count())
+    };
+
+    let mut segments = vec![
+        (format!("{marker} "), color),
+        (tree_prefix(position), None),
+        (item.content.clone(), color),
+    ];
+
+    // Listings which mix projects show the project of each item inline.
+    if let Some(project) = project {
+        let color = color_id_map(&project.color);
+        segments.push((format!(" #{}", project.name), Some(color)));
+    }
+
+    // Show when the item is due relative to today, in red once it's overdue.
+    if let Some(due) = clock.due(item) {
+        let color = clock.is_overdue(due).then_some(term::color::RED);
+        segments.push((format!(" ({})", clock.relative(due)), color));
+    }
+
+    match notes {
+        0 => {}
+        1 => segments.push((String::from(" [1 comment]"), None)),
+        _ => segments.push((format!(" [{notes} comments]"), None)),
+    }
+
+    // Items refer to their labels by name, shared labels which
+    // aren't in our label list are shown without a color.
+    for name in &item.labels {
+        let color = labels
+            .iter()
+            .find(|l| &l.name == name)
+            .map(|l| color_id_map(&l.color));
+        segments.push((format!(" @{name}"), color));
+    }
+
+    print_segments(&segments)
+}
 
-    println!(
-        "{:ident$}{} ({})",
-        "",
-        item.content,
-        item.date_string,
-        ident = ident
+/// Print a comment below the heading of its thread.
+pub fn render_note(
+    note: &types::NoteStruct,
+    author: &str,
+    posted: Option<String>,
+) -> Result<(), TodrError> {
+    let byline = posted.map_or_else(
+        || format!("  {author}"),
+        |posted| format!("  {author}, {posted}"),
     );
+    print_colored(&byline, term::color::BRIGHT_BLACK)?;
+
+    for line in note.content.lines() {
+        println!("    {line}");
+    }
+
+    if let Some(ref attachment) = note.file_attachment {
+        let name = attachment.file_name.as_deref().unwrap_or("file");
+        match attachment.file_url {
+            Some(ref url) => println!("    Attachment: {name} <{url}>"),
+            None => println!("    Attachment: {name}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Print an upcoming reminder, with the item it is for.
+pub fn render_reminder(
+    reminder: &types::ReminderStruct,
+    item: &types::ItemStruct,
+    at: &str,
+    detail: Option<String>,
+) -> Result<(), TodrError> {
+    let mut segments = vec![
+        (format!("  {at}  "), None),
+        (item.content.clone(), priority_color(item.priority)),
+    ];
+
+    if let Some(detail) = detail {
+        segments.push((format!(" ({detail})"), None));
+    }
+
+    segments.push((
+        format!(" [{}]", reminder.id),
+        Some(term::color::BRIGHT_BLACK),
+    ));
+    print_segments(&segments)
+}
+
+#[test]
+fn tree_prefix_test() {
+    let prefix = |last: &[bool]| {
+        let position = TreePosition {
+            last: last.to_vec(),
+        };
+        draw_tree_prefix(&position, &UNICODE_TREE)
+    };
+
+    assert_eq!(prefix(&[]), "");
+    assert_eq!(prefix(&[false]), "");
+    assert_eq!(prefix(&[false, false]), "├─ ");
+    assert_eq!(prefix(&[false, true]), "└─ ");
+    assert_eq!(prefix(&[true, false, true]), "│  └─ ");
+    assert_eq!(prefix(&[true, true, false]), "   ├─ ");
 }
@@ -3,11 +3,15 @@
 use dirs;
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
-use std::path::Path;
 
 // Use our internal handlers module.
+use cache;
 use completer::CustomCompletion;
+use config::Configuration;
 use handlers;
+use handlers::BackgroundSync;
+use output::OutputFormat;
+use state::State;
 
 // On unix platforms you can use ANSI escape sequences
 #[cfg(unix)]
@@ -31,16 +35,28 @@ pub struct Todr {
 
     /// Flag that marks if we should exit or not.
     should_exit: bool,
+
+    /// The locally synced todoist state.
+    state: State,
+
+    /// The refresh of the state which is currently in flight.
+    background_sync: Option<BackgroundSync>,
+
+    /// The format listings are printed in.
+    format: OutputFormat,
 }
 
 impl Todr {
     /// Factory method.
     pub fn new() -> Self {
-        let home = dirs::home_dir().expect("Home Dir couldn't be found");
-        let history_file = Path::new(&home)
-            .join(DEFAULT_HISTORY_FILE)
-            .to_string_lossy()
-            .to_string();
+        let history_file = Configuration::history_file().unwrap_or_else(|| {
+            // Without a home directory the history is kept in the working directory.
+            dirs::home_dir()
+                .unwrap_or_default()
+                .join(DEFAULT_HISTORY_FILE)
+                .to_string_lossy()
+                .to_string()
+        });
 
         let mut editor = Editor::<CustomCompletion>::new();
 
@@ -52,6 +68,28 @@ impl Todr {
             readline_editor: editor,
             history_file,
             should_exit: false,
+            state: cache::load().unwrap_or_else(State::new),
+            background_sync: None,
+            format: OutputFormat::default(),
+        }
+    }
+
+    /// Refresh the cached state without blocking the prompt.
+    fn refresh_in_background(&mut self) {
+        if self.state.is_synced() && self.background_sync.is_none() {
+            self.background_sync = Some(handlers::start_background_sync(&self.state));
+        }
+    }
+
+    /// Pick up the result of the background refresh, if it completed.
+    fn poll_background_sync(&mut self) {
+        let finished = match self.background_sync {
+            Some(ref background) => handlers::finish_background_sync(&mut self.state, background),
+            None => false,
+        };
+
+        if finished {
+            self.background_sync = None;
         }
     }
 
@@ -60,6 +98,9 @@ impl Todr {
         // If loading failed, that's fine, just ignore it.
         let _ = self.readline_editor.load_history(&self.history_file);
 
+        // Whatever we cached last time is likely stale by now.
+        self.refresh_in_background();
+
         loop {
             // Handle graceful exit request.
             if self.should_exit {
@@ -80,36 +121,59 @@ impl Todr {
                     break;
                 }
                 Err(err) => {
-                    println!("Error: {:?}", err);
+                    println!("Error: {err:?}");
                     break;
                 }
             }
         }
 
-        self.readline_editor
-            .save_history(&self.history_file)
-            .expect("Failed to save repl history file");
+        if let Err(e) = self.readline_editor.save_history(&self.history_file) {
+            println!("Failed to save repl history file: {e}");
+        }
     }
 
     /// Processes a single line for a command.
     fn process_line(&mut self, line: &str) {
         self.readline_editor.add_history_entry(line);
+        self.poll_background_sync();
 
-        match line {
-            // Handle printing help message.
-            "help" | "h" => handlers::help_command(),
-
-            // Handle executing the items commands.
-            "items" | "i" => handlers::items_command(),
-
-            // Handle executing the items commands.
-            "projs" | "p" => handlers::projects_command(),
+        // Split the command from its arguments.
+        let mut parts = line.trim().splitn(2, ' ');
+        let command = parts.next().unwrap_or_default();
+        let args = parts.next().unwrap_or_default().trim();
 
+        match command {
             // Handle graceful exit.
             "quit" | "q" => self.should_exit = true,
 
-            // Handle unknown commands.
-            _ => handlers::unknown_command(line),
+            // Show or change the format of the listings for this session.
+            "format" => match args {
+                "" => println!("{}", self.format.name()),
+                _ => match OutputFormat::parse(args) {
+                    Ok(format) => self.format = format,
+                    Err(e) => println!("{e}"),
+                },
+            },
+
+            // Handle the commands shared with the command line.
+            _ => match handlers::Command::parse(command) {
+                Some(command) => {
+                    // Failures are reported, but never end the session.
+                    if let Err(e) =
+                        handlers::execute_command(&mut self.state, command, args, self.format)
+                    {
+                        println!("{e}");
+                    }
+
+                    // Listings are rendered from the cache, so refresh it for next time.
+                    if command.is_listing() {
+                        self.refresh_in_background();
+                    }
+                }
+
+                // Handle unknown commands.
+                None => handlers::unknown_command(line),
+            },
         }
     }
 }
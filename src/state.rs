@@ -0,0 +1,495 @@
+//! This module implements the todr local state.
+//!
+//! The state is seeded by a full sync and then kept up to date by
+//! merging the partial responses of incremental syncs into it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use types;
+
+// The sync token which requests a full sync from the server.
+static FULL_SYNC_TOKEN: &str = "*";
+
+/// Common accessors for the resources we merge into the local state.
+pub trait Resource {
+    /// The identifier of the resource.
+    fn id(&self) -> &str;
+
+    /// Whether the resource is a tombstone marking a deletion.
+    fn is_deleted(&self) -> bool;
+}
+
+impl Resource for types::ItemStruct {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+}
+
+impl Resource for types::ProjectStruct {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+}
+
+impl Resource for types::SectionStruct {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+}
+
+impl Resource for types::LabelStruct {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+}
+
+impl Resource for types::FilterStruct {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+}
+
+impl Resource for types::NoteStruct {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+}
+
+impl Resource for types::ReminderStruct {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+}
+
+impl Resource for types::CollaboratorStruct {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn is_deleted(&self) -> bool {
+        // Collaborators leaving a project are reported through the
+        // collaborator states, the collaborator itself is kept.
+        false
+    }
+}
+
+/// Resources which are nested below a parent of the same kind.
+pub trait Hierarchical: Resource {
+    /// The identifier of the parent, `None` at the top-level.
+    fn parent_id(&self) -> Option<&str>;
+
+    /// The order of the resource among its siblings.
+    fn child_order(&self) -> i64;
+}
+
+impl Hierarchical for types::ItemStruct {
+    fn parent_id(&self) -> Option<&str> {
+        self.parent_id.as_deref()
+    }
+
+    fn child_order(&self) -> i64 {
+        self.child_order
+    }
+}
+
+impl Hierarchical for types::ProjectStruct {
+    fn parent_id(&self) -> Option<&str> {
+        self.parent_id.as_deref()
+    }
+
+    fn child_order(&self) -> i64 {
+        self.child_order
+    }
+}
+
+/// Where a resource is placed in its hierarchy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreePosition {
+    /// For each level from the top-level down to the resource itself,
+    /// whether the resource or its ancestor is the last of its siblings.
+    /// Empty for resources which are listed without their hierarchy.
+    pub last: Vec<bool>,
+}
+
+/// Order resources depth first, so that children follow their parent,
+/// and pair each of them with its position in the hierarchy.
+///
+/// Resources whose parent isn't part of the given set, for example
+/// because it has been completed, are placed at the top-level.
+pub fn hierarchy_order<'a, T: Hierarchical>(resources: &[&'a T]) -> Vec<(TreePosition, &'a T)> {
+    let ids: HashSet<&str> = resources.iter().map(|r| r.id()).collect();
+
+    let mut children: BTreeMap<Option<&str>, Vec<&'a T>> = BTreeMap::new();
+    for resource in resources {
+        let parent_id = resource.parent_id().filter(|id| ids.contains(id));
+        children.entry(parent_id).or_default().push(resource);
+    }
+
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|r| r.child_order());
+    }
+
+    let mut ordered = Vec::with_capacity(resources.len());
+    visit_children(&children, None, &TreePosition::default(), &mut ordered);
+    ordered
+}
+
+fn visit_children<'a, T: Hierarchical>(
+    children: &BTreeMap<Option<&str>, Vec<&'a T>>,
+    parent_id: Option<&str>,
+    parent: &TreePosition,
+    ordered: &mut Vec<(TreePosition, &'a T)>,
+) {
+    let Some(siblings) = children.get(&parent_id) else {
+        return;
+    };
+
+    for (index, resource) in siblings.iter().enumerate() {
+        let mut position = parent.clone();
+        position.last.push(index + 1 == siblings.len());
+
+        ordered.push((position.clone(), resource));
+        visit_children(children, Some(resource.id()), &position, ordered);
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct State {
+    /// The token to send on the next sync request.
+    pub sync_token: String,
+
+    /// All items known locally.
+    pub items: Vec<types::ItemStruct>,
+
+    /// All projects known locally.
+    pub projects: Vec<types::ProjectStruct>,
+
+    /// All project sections known locally.
+    pub sections: Vec<types::SectionStruct>,
+
+    /// All labels known locally.
+    pub labels: Vec<types::LabelStruct>,
+
+    /// All saved filters known locally.
+    pub filters: Vec<types::FilterStruct>,
+
+    /// All item notes known locally.
+    pub notes: Vec<types::NoteStruct>,
+
+    /// All project notes known locally.
+    pub project_notes: Vec<types::NoteStruct>,
+
+    /// All reminders known locally.
+    pub reminders: Vec<types::ReminderStruct>,
+
+    /// All users sharing projects with the user.
+    pub collaborators: Vec<types::CollaboratorStruct>,
+
+    /// The users information.
+    pub user: Option<types::UserStruct>,
+}
+
+impl State {
+    /// Factory method, the empty state requests a full sync.
+    pub fn new() -> Self {
+        Self {
+            sync_token: String::from(FULL_SYNC_TOKEN),
+            items: Vec::new(),
+            projects: Vec::new(),
+            sections: Vec::new(),
+            labels: Vec::new(),
+            filters: Vec::new(),
+            notes: Vec::new(),
+            project_notes: Vec::new(),
+            reminders: Vec::new(),
+            collaborators: Vec::new(),
+            user: None,
+        }
+    }
+
+    /// Whether the state has been seeded by a full sync yet.
+    pub fn is_synced(&self) -> bool {
+        self.sync_token != FULL_SYNC_TOKEN
+    }
+
+    /// Merge a sync response into the local state.
+    ///
+    /// A full sync replaces everything we know, while an incremental
+    /// sync only carries the resources which changed since the last
+    /// sync token, including tombstones for deleted resources.
+    pub fn merge(&mut self, sync_state: types::SyncStruct) {
+        if sync_state.full_sync {
+            self.items.clear();
+            self.projects.clear();
+            self.sections.clear();
+            self.labels.clear();
+            self.filters.clear();
+            self.notes.clear();
+            self.project_notes.clear();
+            self.reminders.clear();
+            self.collaborators.clear();
+        }
+
+        if let Some(items) = sync_state.items {
+            merge_resources(&mut self.items, items);
+        }
+
+        if let Some(projects) = sync_state.projects {
+            merge_resources(&mut self.projects, projects);
+        }
+
+        if let Some(sections) = sync_state.sections {
+            merge_resources(&mut self.sections, sections);
+        }
+
+        if let Some(labels) = sync_state.labels {
+            merge_resources(&mut self.labels, labels);
+        }
+
+        if let Some(filters) = sync_state.filters {
+            merge_resources(&mut self.filters, filters);
+        }
+
+        if let Some(notes) = sync_state.notes {
+            merge_resources(&mut self.notes, notes);
+        }
+
+        if let Some(project_notes) = sync_state.project_notes {
+            merge_resources(&mut self.project_notes, project_notes);
+        }
+
+        if let Some(reminders) = sync_state.reminders {
+            merge_resources(&mut self.reminders, reminders);
+        }
+
+        if let Some(collaborators) = sync_state.collaborators {
+            merge_resources(&mut self.collaborators, collaborators);
+        }
+
+        if sync_state.user.is_some() {
+            self.user = sync_state.user;
+        }
+
+        self.sync_token = sync_state.sync_token;
+    }
+
+    /// Find a project by its id.
+    pub fn project(&self, id: &str) -> Option<&types::ProjectStruct> {
+        self.projects.iter().find(|p| p.id == id)
+    }
+
+    /// Find a section by its id.
+    pub fn section(&self, id: &str) -> Option<&types::SectionStruct> {
+        self.sections.iter().find(|s| s.id == id)
+    }
+
+    /// The notes on an item, oldest first.
+    pub fn notes_on_item(&self, item_id: &str) -> Vec<&types::NoteStruct> {
+        sorted_notes(
+            self.notes
+                .iter()
+                .filter(|n| n.item_id.as_deref() == Some(item_id)),
+        )
+    }
+
+    /// The notes on a project, oldest first.
+    pub fn notes_on_project(&self, project_id: &str) -> Vec<&types::NoteStruct> {
+        sorted_notes(
+            self.project_notes
+                .iter()
+                .filter(|n| n.project_id.as_deref() == Some(project_id)),
+        )
+    }
+
+    /// The name of the user with the given id, either the user
+    /// themselves or one of their collaborators.
+    pub fn user_name(&self, uid: &str) -> Option<&str> {
+        match self.user {
+            Some(ref user) if user.id == uid => Some(&user.full_name),
+            _ => self
+                .collaborators
+                .iter()
+                .find(|c| c.id == uid)
+                .map(|c| c.full_name.as_str()),
+        }
+    }
+}
+
+fn sorted_notes<'a>(
+    notes: impl Iterator<Item = &'a types::NoteStruct>,
+) -> Vec<&'a types::NoteStruct> {
+    let mut notes: Vec<&types::NoteStruct> = notes.collect();
+
+    // RFC 3339 timestamps in UTC sort chronologically.
+    notes.sort_by(|a, b| a.posted_at.cmp(&b.posted_at));
+    notes
+}
+
+/// Replace each local resource with its updated version, dropping
+/// the ones the server reports as deleted.
+fn merge_resources<T: Resource>(local: &mut Vec<T>, delta: Vec<T>) {
+    for resource in delta {
+        local.retain(|r| r.id() != resource.id());
+
+        if !resource.is_deleted() {
+            local.push(resource);
+        }
+    }
+}
+
+#[cfg(test)]
+use serde_json;
+
+/// A user for tests, with the given id.
+#[cfg(test)]
+pub fn test_user(id: &str) -> types::UserStruct {
+    serde_json::from_value(json!({
+        "id": id, "email": "jane@example.com", "full_name": "Jane Doe",
+        "inbox_project_id": "1",
+        "tz_info": { "hours": 1, "minutes": 0, "gmt_string": "+01:00", "is_dst": 0,
+                     "timezone": "Europe/Berlin" },
+        "start_page": "today", "start_day": 1, "next_week": 1, "date_format": 0,
+        "time_format": 0, "sort_order": 0, "lang": "en",
+        "features": { "karma_disabled": false, "restriction": 3, "karma_vacation": false,
+                      "beta": 0, "has_push_reminders": true, "dateist_inline_disabled": false },
+        "is_premium": true, "premium_until": null, "auto_reminder": 30, "daily_goal": 5,
+        "weekly_goal": 25, "karma": 0.0, "karma_trend": "up", "joined_at": null,
+        "image_id": null, "avatar_small": null, "avatar_medium": null, "avatar_big": null,
+        "avatar_s640": null, "business_account_id": null, "is_biz_admin": false,
+        "token": "0123456789abcdef"
+    }))
+    .unwrap()
+}
+
+/// An active item for tests, in project 10 and without a due date.
+///
+/// Tests set the fields they are about on the returned item.
+#[cfg(test)]
+pub fn test_item(id: &str, content: &str) -> types::ItemStruct {
+    serde_json::from_value(json!({
+        "id": id, "user_id": "1", "project_id": "10", "content": content,
+        "description": "", "priority": 1, "due": null, "parent_id": null,
+        "child_order": 1, "section_id": null, "day_order": -1, "collapsed": false,
+        "labels": [], "added_by_uid": null, "assigned_by_uid": null,
+        "responsible_uid": null, "checked": false, "is_deleted": false, "sync_id": null
+    }))
+    .unwrap()
+}
+
+/// A due date for tests which doesn't recur.
+#[cfg(test)]
+pub fn test_due(date: &str) -> types::DueStruct {
+    serde_json::from_value(json!({
+        "date": date, "timezone": null, "string": "", "lang": "en", "is_recurring": false
+    }))
+    .unwrap()
+}
+
+#[cfg(test)]
+fn sync_response(full_sync: bool, items: &[types::ItemStruct]) -> types::SyncStruct {
+    serde_json::from_value(json!({
+        "full_sync": full_sync, "items": items, "sync_token": "next"
+    }))
+    .unwrap()
+}
+
+#[test]
+fn merge_incremental_test() {
+    let mut state = State::new();
+    assert_eq!(state.sync_token, "*");
+
+    state.merge(sync_response(
+        true,
+        &[test_item("1", "One"), test_item("2", "Two")],
+    ));
+    assert_eq!(state.sync_token, "next");
+    assert_eq!(state.items.len(), 2);
+
+    // Update one item, delete the other and add a new one.
+    let mut deleted = test_item("2", "Two");
+    deleted.is_deleted = true;
+    state.merge(sync_response(
+        false,
+        &[test_item("1", "Uno"), deleted, test_item("3", "Three")],
+    ));
+
+    let contents: Vec<&str> = state.items.iter().map(|i| i.content.as_str()).collect();
+    assert_eq!(contents, vec!["Uno", "Three"]);
+}
+
+#[test]
+fn merge_full_sync_replaces_test() {
+    let mut state = State::new();
+
+    state.merge(sync_response(true, &[test_item("1", "One")]));
+    state.merge(sync_response(true, &[test_item("2", "Two")]));
+
+    assert_eq!(state.items.len(), 1);
+    assert_eq!(state.items[0].id, "2");
+}
+
+#[test]
+fn hierarchy_order_test() {
+    let item = |id: &str, content: &str, parent_id: Option<&str>, child_order: i64| {
+        let mut item = test_item(id, content);
+        item.parent_id = parent_id.map(String::from);
+        item.child_order = child_order;
+        item
+    };
+
+    let mut state = State::new();
+    state.merge(sync_response(
+        true,
+        &[
+            item("1", "Second", None, 2),
+            item("2", "First", None, 1),
+            item("3", "Child", Some("2"), 1),
+            item("4", "Orphan", Some("42"), 3),
+        ],
+    ));
+
+    let items: Vec<&types::ItemStruct> = state.items.iter().collect();
+    let ordered: Vec<(Vec<bool>, &str)> = hierarchy_order(&items)
+        .into_iter()
+        .map(|(position, i)| (position.last, i.content.as_str()))
+        .collect();
+
+    assert_eq!(
+        ordered,
+        vec![
+            (vec![false], "First"),
+            (vec![false, true], "Child"),
+            (vec![false], "Second"),
+            (vec![true], "Orphan")
+        ]
+    );
+}
@@ -0,0 +1,250 @@
+//! This module implements the todo.txt format.
+//!
+//! Each line of a todo.txt file is a task, such as
+//! `x 2024-03-02 2024-03-01 (A) Call mom +Family @phone due:2024-03-05`,
+//! with its completion, priority and dates in front of the description,
+//! and its projects, contexts and other metadata as tags within it.
+//!
+//! Link: <https://github.com/todotxt/todo.txt>
+
+use chrono::NaiveDate;
+use std::fmt;
+
+/// A task parsed from a line of a todo.txt file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Task {
+    /// Whether the line is marked as done with an `x`.
+    pub completed: bool,
+
+    /// The priority, from `A` for the most urgent to `Z`.
+    pub priority: Option<char>,
+
+    /// The day the task was completed on.
+    pub completion_date: Option<NaiveDate>,
+
+    /// The day the task was created on.
+    pub creation_date: Option<NaiveDate>,
+
+    /// The description, without the projects, contexts and due date.
+    pub description: String,
+
+    /// The `+project` tags, in the order they appear in.
+    pub projects: Vec<String>,
+
+    /// The `@context` tags, in the order they appear in.
+    pub contexts: Vec<String>,
+
+    /// The `due:YYYY-MM-DD` date.
+    pub due: Option<NaiveDate>,
+}
+
+impl Task {
+    /// Parse a line, `None` for blank lines.
+    pub fn parse(line: &str) -> Option<Self> {
+        if line.trim().is_empty() {
+            return None;
+        }
+
+        let mut task = Self::default();
+        let mut words = line.split_whitespace().peekable();
+
+        if words.peek() == Some(&"x") {
+            task.completed = true;
+            words.next();
+        }
+
+        if let Some(priority) = words.peek().and_then(|word| parse_priority(word)) {
+            task.priority = Some(priority);
+            words.next();
+        }
+
+        // Completed tasks carry their completion date first, then their
+        // creation date, while other tasks only carry their creation date.
+        let max_dates = if task.completed { 2 } else { 1 };
+        let mut dates = Vec::new();
+        while dates.len() < max_dates {
+            match words.peek().and_then(|word| parse_date(word)) {
+                Some(date) => dates.push(date),
+                None => break,
+            }
+            words.next();
+        }
+
+        match (task.completed, dates.as_slice()) {
+            (true, [completed, created]) => {
+                task.completion_date = Some(*completed);
+                task.creation_date = Some(*created);
+            }
+            (true, [completed]) => task.completion_date = Some(*completed),
+            (false, [created, ..]) => task.creation_date = Some(*created),
+            _ => {}
+        }
+
+        let mut description = Vec::new();
+        for word in words {
+            if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+                task.projects.push(String::from(project));
+            } else if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+                task.contexts.push(String::from(context));
+            } else if let Some(due) = word.strip_prefix("due:").and_then(parse_date) {
+                task.due = Some(due);
+            } else if let (Some(priority), None) = (word.strip_prefix("pri:"), task.priority) {
+                // The priority of completed tasks is kept as a tag,
+                // as the `x` has to come first on the line.
+                task.priority = parse_priority(&format!("({priority})"));
+            } else {
+                description.push(word);
+            }
+        }
+
+        task.description = description.join(" ");
+        Some(task)
+    }
+
+    /// The todoist priority of the API, from 4 for `(A)` down to 1 for `(D)` and below.
+    pub const fn api_priority(&self) -> u8 {
+        match self.priority {
+            Some('A') => 4,
+            Some('B') => 3,
+            Some('C') => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut words = Vec::new();
+
+        if self.completed {
+            words.push(String::from("x"));
+        } else if let Some(priority) = self.priority {
+            words.push(format!("({priority})"));
+        }
+
+        // A creation date can only follow a completion date on completed tasks.
+        let completion_date = self.completion_date.filter(|_| self.completed);
+        let creation_date = self
+            .creation_date
+            .filter(|_| !self.completed || completion_date.is_some());
+        words.extend(completion_date.map(|date| date.format("%Y-%m-%d").to_string()));
+        words.extend(creation_date.map(|date| date.format("%Y-%m-%d").to_string()));
+
+        if !self.description.is_empty() {
+            words.push(self.description.clone());
+        }
+
+        // Tags end at whitespace, so it is replaced in the names.
+        let tag = |prefix: &str, name: &str| {
+            let name: Vec<&str> = name.split_whitespace().collect();
+            format!("{prefix}{}", name.join("_"))
+        };
+        words.extend(self.projects.iter().map(|project| tag("+", project)));
+        words.extend(self.contexts.iter().map(|context| tag("@", context)));
+        words.extend(
+            self.due
+                .map(|due| format!("due:{}", due.format("%Y-%m-%d"))),
+        );
+
+        if self.completed {
+            words.extend(self.priority.map(|priority| format!("pri:{priority}")));
+        }
+
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+/// The todo.txt priority of a todoist priority of the API, the reverse of `api_priority`.
+pub const fn priority_from_api(priority: u8) -> Option<char> {
+    match priority {
+        4 => Some('A'),
+        3 => Some('B'),
+        2 => Some('C'),
+        _ => None,
+    }
+}
+
+/// Parse the tasks of a todo.txt file with their line numbers, skipping blank lines.
+pub fn parse(contents: &str) -> Vec<(usize, Task)> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| Task::parse(line).map(|task| (index + 1, task)))
+        .collect()
+}
+
+/// Parse a priority such as `(A)`.
+fn parse_priority(word: &str) -> Option<char> {
+    let priority = word.strip_prefix('(')?.strip_suffix(')')?;
+    let mut chars = priority.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(priority), None) if priority.is_ascii_uppercase() => Some(priority),
+        _ => None,
+    }
+}
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+#[test]
+fn parse_task_test() {
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+
+    let task = Task::parse("(A) 2024-03-01 Call mom +Family @phone due:2024-03-05 url:x").unwrap();
+    assert!(!task.completed);
+    assert_eq!(task.priority, Some('A'));
+    assert_eq!(task.api_priority(), 4);
+    assert_eq!(task.creation_date, date(2024, 3, 1));
+    assert_eq!(task.description, "Call mom url:x");
+    assert_eq!(task.projects, vec!["Family"]);
+    assert_eq!(task.contexts, vec!["phone"]);
+    assert_eq!(task.due, date(2024, 3, 5));
+
+    let task = Task::parse("x 2024-03-02 2024-03-01 Pay rent pri:B").unwrap();
+    assert!(task.completed);
+    assert_eq!(task.completion_date, date(2024, 3, 2));
+    assert_eq!(task.creation_date, date(2024, 3, 1));
+    assert_eq!(task.priority, Some('B'));
+    assert_eq!(task.description, "Pay rent");
+
+    // Only a lone lowercase `x` marks a task as done.
+    let task = Task::parse("xylophone lessons (A)").unwrap();
+    assert!(!task.completed);
+    assert_eq!(task.priority, None);
+    assert_eq!(task.api_priority(), 1);
+    assert_eq!(task.description, "xylophone lessons (A)");
+
+    // Only completed tasks carry two dates, others keep the second one.
+    let task = Task::parse("2024-03-01 2024-03-05 dentist").unwrap();
+    assert_eq!(task.creation_date, date(2024, 3, 1));
+    assert_eq!(task.completion_date, None);
+    assert_eq!(task.description, "2024-03-05 dentist");
+
+    assert_eq!(Task::parse("   "), None);
+
+    let tasks = parse("Buy milk\n\nBuy eggs\n");
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[1].0, 3);
+}
+
+#[test]
+fn format_task_test() {
+    let lines = [
+        "(A) 2024-03-01 Call mom +Family @phone due:2024-03-05",
+        "x 2024-03-02 2024-03-01 Pay rent +Home pri:B",
+        "Buy milk",
+    ];
+
+    for line in lines {
+        assert_eq!(Task::parse(line).unwrap().to_string(), line);
+    }
+
+    let task = Task {
+        description: String::from("Plan trip"),
+        projects: vec![String::from("Summer holidays")],
+        ..Task::default()
+    };
+    assert_eq!(task.to_string(), "Plan trip +Summer_holidays");
+}
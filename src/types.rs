@@ -3,9 +3,11 @@
 ///! messages when interacting with the todoist API.
 ///!
 ///! Types manually converted from documentation here:
-///!  - [Todoist API](https://developer.todoist.com/)
+///!  - [Todoist Sync API](https://developer.todoist.com/sync/v9/)
 ///!
-///! Note: These are using v7 definition of the todoist API.
+///! Note: These are using v9 definition of the todoist API.
+///! The retired v7 definitions live in the `v7` module,
+///! which is only built with the `api-v7` feature.
 ///!
 ///! We attribute each of the structs with:
 ///!
@@ -13,6 +15,11 @@
 ///! - 'Debug ' impl for ... debugging.
 ///!
 use serde::{Deserialize, Serialize};
+use serde_json;
+use std::collections::HashMap;
+
+#[cfg(feature = "api-v7")]
+pub mod v7;
 
 /// Struct to be used for objects which we don't
 /// yet support. It doesn't deserialize anything.
@@ -21,7 +28,50 @@ use serde::{Deserialize, Serialize};
 pub struct NotYetUsedStruct {}
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct LabelStruct {}
+pub struct LabelStruct {
+    /// The identifier of this label.
+    pub id: String,
+
+    /// The name of the label, items refer to their labels by it.
+    pub name: String,
+
+    /// The color of the label, see `ProjectStruct::color`.
+    pub color: String,
+
+    /// Label's order in the label list (a number, where the
+    /// smallest value should place the label at the top).
+    pub item_order: i64,
+
+    /// Is the label deleted.
+    pub is_deleted: bool,
+
+    /// Whether the label is a favorite.
+    pub is_favorite: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DueStruct {
+    /// The due date, for recurring tasks the date of the current occurrence.
+    ///
+    /// All day tasks only have a date (`2016-12-01`), tasks with a time
+    /// are floating (`2016-12-01T12:00:00`) or fixed to a timezone
+    /// and given in UTC (`2016-12-01T12:00:00Z`).
+    pub date: String,
+
+    /// The timezone of a fixed due date, null for floating dates.
+    pub timezone: Option<String>,
+
+    /// The date as entered by the user, in free form text,
+    /// for example it can be every day @ 10.
+    pub string: String,
+
+    /// The language of the `string`.
+    /// Valid languages are: en, da, pl, zh, ko, de, pt, ja, it, fr, sv, ru, es, nl
+    pub lang: String,
+
+    /// Whether the task has a recurring due date.
+    pub is_recurring: bool,
+}
 
 // Automatically generate:
 // - `Serialize` and `Deserialize` impls for serialization.
@@ -29,88 +79,74 @@ pub struct LabelStruct {}
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ItemStruct {
     /// The id of the task.
-    pub id: u64,
-
-    pub all_day: bool,
+    pub id: String,
 
     /// The owner of the task.
-    pub user_id: u64,
+    pub user_id: String,
 
     /// The project that the task resides in.
-    pub project_id: u64,
-
-    /// The parent id of the task.
-    pub parent_id: Option<u64>,
+    pub project_id: String,
 
     /// The text of the task.
     pub content: String,
 
-    /// The date of the task, added in free form text,
-    /// for example it can be every day @ 10 (or null or an empty string if not set).
-    pub date_string: String,
-
-    /// The language of the date_string.
-    /// Valid languages are: en, da, pl, zh, ko, de, pt, ja, it, fr, sv, ru, es, nl
-    pub date_lang: String,
+    /// A description for the task.
+    #[serde(default)]
+    pub description: String,
 
-    /// The date of the task in the format:
-    /// Mon 07 Aug 2006 12:34:56 +0000 (or null if not set).
-    ///
-    /// For all day task (i.e. task due “Today”),
-    /// the time part will be set as xx:xx:59.
-    pub due_date_utc: Option<String>,
+    /// The due date of the task (or null if not set).
+    pub due: Option<DueStruct>,
 
     /// The priority of the task between: 1..4.
     /// - 1 is natural
     /// - 4 is very urgent
     pub priority: u8,
 
-    /// The indent of the task between: 1..4.
-    /// - 1 is top-level
-    pub indent: u8,
+    /// The parent id of the task, null for top-level tasks.
+    pub parent_id: Option<String>,
 
-    /// The order of the task inside the project.
+    /// The order of the task among its siblings,
     /// smallest value is placed at the top.
-    pub item_order: u64,
+    pub child_order: i64,
+
+    /// The section the task resides in, null if it isn't in one.
+    pub section_id: Option<String>,
 
     /// The order of the task inside the Today or Next 7 days view
     /// (a number, where the smallest value would place the task at the top).
     pub day_order: i64,
 
     /// Whether the task's sub-tasks are collapsed.
-    pub collapsed: u8,
+    pub collapsed: bool,
+
+    /// The names of the task's labels.
+    pub labels: Vec<String>,
 
-    /// The tasks label ids.
-    pub labels: Vec<u64>,
+    /// The id of the user who created the task.
+    pub added_by_uid: Option<String>,
 
     /// The id of the user who assigned the current task.
     /// Only makes sense for shared projects.
-    pub assigned_by_uid: Option<u64>,
+    pub assigned_by_uid: Option<String>,
 
     /// The id of user who is responsible for the current task.
     /// Only makes sense for shared projects.
-    pub responsible_uid: Option<u64>,
+    pub responsible_uid: Option<String>,
 
     /// Whether the task is marked as completed.
-    pub checked: u8,
-
-    /// Whether the task hsa been marked as completed is marked to be moved to history.
-    pub in_history: u8,
+    pub checked: bool,
 
     /// Whether the task is marked as deleted.
-    pub is_deleted: u8,
-
-    /// Whether the task is marked as archived.
-    pub is_archived: u8,
+    pub is_deleted: bool,
 
     /// A special id for shared tasks. Can be ignored.
-    pub sync_id: Option<u64>,
+    pub sync_id: Option<String>,
 
     /// The date when the task was created.
-    pub date_added: Option<String>,
+    pub added_at: Option<String>,
 
     /// The date when the task was completed.
-    pub date_completed: Option<String>,
+    pub completed_at: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -131,218 +167,341 @@ pub struct UserFeaturesStruct {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TimeZoneStruct {
     /// Hours difference from GMT.
-    hours: i8,
+    pub hours: i8,
 
     /// Minutes difference from GMT.
-    minutes: i8,
+    pub minutes: i8,
 
     /// Time difference from GMT as a string.
-    gmt_string: String,
+    pub gmt_string: String,
 
     /// Whether daylight saving time applies.
-    is_dst: u8,
+    pub is_dst: u8,
 
     /// The timezone name.
-    timezone: String,
+    pub timezone: String,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct UserStruct {
-    /// URL to the small size of the user avatar.
-    pub avatar_small: String,
+    /// The users identification number.
+    pub id: String,
 
-    /// The users avatar in a medium size.
-    pub avatar_medium: String,
+    /// The users email address.
+    pub email: String,
 
-    /// The users avatar in a big size.
-    pub avatar_big: String,
+    /// The users full name.
+    pub full_name: String,
 
-    /// The users avatar in yet another resolution.
-    pub avatar_s640: String,
+    /// The project the user has selected to use as their inbox.
+    pub inbox_project_id: String,
+
+    /// Users timezone information.
+    pub tz_info: TimeZoneStruct,
 
     /// The users start page.
     pub start_page: String,
 
-    /// What features the user has set.
-    pub features: UserFeaturesStruct,
+    /// The first day of the week (between 1 (Monday) and 7 (Sunday))
+    pub start_day: u8,
+
+    /// The day of the next week, that tasks will be postponed to.
+    /// (Between 1 and 7, where Monday is 1 and 7 is Sunday)
+    pub next_week: u8,
 
-    /// How many tasks the user has completed today.
-    pub completed_today: u32,
+    /// Whether to use the DD-MM-YYYY date format (if set to 0),
+    /// or the MM-DD-YYYY format (if set to 1).
+    pub date_format: u8,
 
-    /// Is this a premium user.
-    pub is_premium: bool,
+    /// Whether to use a 24h format such as 13:00 (if set to 0) when displaying time,
+    /// or a 12h format such as 1:00pm (if set to 1).
+    pub time_format: u8,
 
     /// If projects should be shown in oldest dates first (value = 0)
     /// or oldest dates last (value = 1)
     pub sort_order: u8,
 
-    /// The users full name.
-    pub full_name: String,
-
-    /// The default time in minutes for the automatic reminders.
-    pub auto_reminder: u32,
+    /// The users language.
+    pub lang: String,
 
-    /// The date the user joined.
-    pub join_date: Option<String>,
+    /// What features the user has set.
+    pub features: UserFeaturesStruct,
 
-    /// The users identification number.
-    pub id: u64,
+    /// Is this a premium user.
+    pub is_premium: bool,
 
-    /// The day of the next week, that tasks will be postponed to.
-    /// (Between 1 and y, where Monday is 1 and 7 is Sunday)
-    pub next_week: u8,
+    /// The date when the users's Premium subscrpition ends.
+    pub premium_until: Option<String>,
 
-    /// The total number tasks the user has completed.
-    pub completed_count: u64,
+    /// The default time in minutes for the automatic reminders.
+    pub auto_reminder: u32,
 
     /// The daily goal of tasks.
     pub daily_goal: u32,
 
-    /// The currently selected Todoist theme (between 0 and 10).
-    pub theme: u8,
-
-    /// The users email address.
-    pub email: String,
-
-    /// The first day of the week (between 1 (Monday) and 7 (Sunday))
-    pub start_day: u8,
-
-    /// Users timezone information.
-    pub tz_info: TimeZoneStruct,
+    /// The weekly goal of tasks.
+    pub weekly_goal: u32,
 
-    /// Whether to use the DD-MM-YYYY date format (if set to 0),
-    /// or the MM-DD-YYYY format (if set to 1).
-    pub date_format: u8,
-
-    /// The project the user has selected to use as their inbox.
-    pub inbox_project: u64,
-
-    /// Whether to use a 24h format such as 13:00 (if set to 0) when displaying time,
-    /// or a 12h format such as 1:00pm (if set to 1).
-    pub time_format: u8,
-
-    /// The id of the user's avatar.
-    pub image_id: String,
+    /// The user's karma score.
+    pub karma: f64,
 
     /// The user's karma trend.
     pub karma_trend: String,
 
-    /// The id o the user's business account.
-    pub business_account_id: Option<u64>,
+    /// The date the user joined.
+    pub joined_at: Option<String>,
 
-    /// The users mobile phone number.
-    pub mobile_number: Option<String>,
+    /// The id of the user's avatar.
+    pub image_id: Option<String>,
 
-    /// The users mobile host number.
-    pub mobile_host: Option<String>,
+    /// URL to the small size of the user avatar.
+    pub avatar_small: Option<String>,
 
-    /// The date when the users's Premium subscrpition ends.
-    pub premium_until: Option<String>,
+    /// The users avatar in a medium size.
+    pub avatar_medium: Option<String>,
 
-    /// The users authentication token.
-    pub token: String,
+    /// The users avatar in a big size.
+    pub avatar_big: Option<String>,
 
-    /// The user's karma score.
-    pub karma: u64,
+    /// The users avatar in yet another resolution.
+    pub avatar_s640: Option<String>,
+
+    /// The id o the user's business account.
+    pub business_account_id: Option<String>,
 
     /// Whether the user is a business account admin.
     pub is_biz_admin: bool,
-
-    /// The default reminder for the user. Reminders are only possible for Premium users.
-    /// The default reminder can be one of the following: email to send reminders by email,
-    /// mobile to send reminders to mobile devices via SMS, push to send reminders to smart
-    /// devices using push notifications (one of the Android or iOS official clients must be
-    /// installed on the client side to receive these notifications),
-    /// no_default to turn off sending default reminders.
-    pub default_reminder: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FilterStruct {
-    /// The presentation order of the filter.
-    item_order: u8,
+    /// The identifier of this filter.
+    pub id: String,
 
     /// The name of the filter.
-    name: String,
+    pub name: String,
 
     /// The query specifier for this filter.
-    query: String,
+    pub query: String,
 
     /// The color to present this filter as.
-    color: u8,
+    pub color: String,
+
+    /// The presentation order of the filter.
+    pub item_order: i64,
 
     /// Is the filter deleted.
-    is_deleted: u8,
+    pub is_deleted: bool,
 
-    /// The identifier of this filter.
-    id: u64,
+    /// Whether the filter is a favorite.
+    pub is_favorite: bool,
+}
+
+/// A file or link attached to a note.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileAttachmentStruct {
+    /// The name of the file.
+    pub file_name: Option<String>,
+
+    /// The MIME type of the file.
+    pub file_type: Option<String>,
+
+    /// The URL the file can be downloaded from.
+    pub file_url: Option<String>,
+
+    /// The kind of attachment, for example 'file', 'image' or 'url'.
+    pub resource_type: Option<String>,
+}
+
+/// A comment on an item or a project.
+///
+/// Item notes and project notes are synced as separate resources,
+/// with either the `item_id` or the `project_id` set.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NoteStruct {
+    /// The identifier of this note.
+    pub id: String,
+
+    /// The id of the user who posted the note.
+    pub posted_uid: Option<String>,
+
+    /// The item the note is on, for item notes.
+    #[serde(default)]
+    pub item_id: Option<String>,
+
+    /// The project the note is on, for project notes.
+    #[serde(default)]
+    pub project_id: Option<String>,
+
+    /// The text of the note.
+    pub content: String,
+
+    /// A file or link attached to the note.
+    pub file_attachment: Option<FileAttachmentStruct>,
+
+    /// When the note was posted.
+    pub posted_at: Option<String>,
+
+    /// Is the note deleted.
+    pub is_deleted: bool,
+}
+
+/// A user who shares a project with the user.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CollaboratorStruct {
+    /// The identifier of the collaborating user.
+    pub id: String,
+
+    /// The email address of the collaborator.
+    pub email: String,
+
+    /// The full name of the collaborator.
+    pub full_name: String,
+
+    /// The timezone of the collaborator.
+    pub timezone: Option<String>,
+
+    /// The id of the avatar of the collaborator.
+    pub image_id: Option<String>,
+}
+
+/// A task as returned by the REST API.
+///
+/// Only the id is used, to look up the synced `ItemStruct`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskStruct {
+    /// The id of the task, the same as the id of the item.
+    pub id: String,
+}
+
+/// A page of the completed items, which syncs leave out.
+///
+/// Link: <https://developer.todoist.com/sync/v9/#get-all-completed-items>
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CompletedStruct {
+    /// The completed items, the most recently completed first.
+    pub items: Vec<CompletedItemStruct>,
+}
+
+/// A completed item, as listed by the completed items endpoint.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CompletedItemStruct {
+    /// The id of the item.
+    pub task_id: String,
+
+    /// The date when the item was completed.
+    pub completed_at: String,
+
+    /// The item itself, only sent when asked for with `annotate_items`.
+    pub item_object: Option<ItemStruct>,
+}
+
+/// When a reminder goes off.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReminderType {
+    /// A number of minutes before the item is due.
+    Relative,
+
+    /// At a specific date and time.
+    Absolute,
+
+    /// When arriving at or leaving a location.
+    Location,
+
+    /// Kinds of reminders added to the API after this was written.
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ReminderStruct {
-    /// The item id that this reminder is for.
-    item_id: u64,
+    /// The identifier of this reminder.
+    pub id: String,
 
-    /// The alias name for the location.
-    name: String,
+    /// The user who is notified by this reminder.
+    pub notify_uid: Option<String>,
 
-    /// The service to get the reminder for.
-    service: String,
+    /// The item id that this reminder is for.
+    pub item_id: String,
 
     /// The type of reminder, 'relative' for time-based reminder in minutes.
     /// 'absolute' for a time-based reminder with a specific time and date in the future.
     /// 'location' for a location-based reminder.
-    /// type: String,
+    #[serde(rename = "type")]
+    pub reminder_type: ReminderType,
 
-    /// The date of the task, added in free form text,
-    /// for example it can be every day @ 10 (or null or an empty string if not set).
-    date_string: String,
-
-    /// The language of the date_string.
-    /// Valid languages are: en, da, pl, zh, ko, de, pt, ja, it, fr, sv, ru, es, nl
-    date_lang: String,
+    /// When an absolute reminder goes off.
+    pub due: Option<DueStruct>,
 
-    /// Is the reminder deleted (0,1).
-    is_deleted: u8,
+    /// How many minutes before the item is due a relative reminder goes off.
+    pub minute_offset: Option<i64>,
 
-    /// The identifier of this filter.
-    id: u64,
+    /// Is the reminder deleted.
+    pub is_deleted: bool,
 }
 
+// The flags mirror the API, they aren't ours to group.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ProjectStruct {
+    /// The identifier of this project.
+    pub id: String,
+
     /// The name of the project.
     pub name: String,
 
-    /// The id of the parent project.
-    pub parent_id: Option<u64>,
+    /// The color of the project icon, by name, for example `berry_red`.
+    pub color: String,
 
-    /// The color to present this filter as.
-    pub color: u8,
+    /// The id of the parent project, null for top-level projects.
+    pub parent_id: Option<String>,
 
-    /// Whether the project's sub-projects are collapsed (0,1)
-    pub collapsed: u8,
+    /// The order of the project among its siblings.
+    pub child_order: i64,
+
+    /// Whether the project's sub-projects are collapsed.
+    pub collapsed: bool,
+
+    /// Whether the project is shared.
+    pub shared: bool,
 
     /// This project is marked as the inbox project.
-    pub inbox_project: Option<bool>,
+    #[serde(default)]
+    pub inbox_project: bool,
 
-    /// This project has more notes.
-    pub has_more_notes: Option<bool>,
+    /// Whether the project is a favorite.
+    pub is_favorite: bool,
 
-    /// The item order (0,1).
-    pub item_order: u8,
+    /// Is the project deleted.
+    pub is_deleted: bool,
 
-    // The indent of the time (1..4) where 1 is top level.
-    pub indent: u8,
+    /// Whether the project is marked as archived.
+    pub is_archived: bool,
+}
 
-    /// The identifier of this filter.
-    pub id: u64,
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SectionStruct {
+    /// The identifier of this section.
+    pub id: String,
+
+    /// The name of the section.
+    pub name: String,
+
+    /// The project the section resides in.
+    pub project_id: String,
 
-    /// Is the project deleted (0,1).
-    pub is_deleted: u8,
+    /// The order of the section inside the project.
+    pub section_order: i64,
 
-    /// Whether the project is marked as archived (0,1)
-    pub is_archived: u8,
+    /// Whether the section's tasks are collapsed.
+    pub collapsed: bool,
+
+    /// Is the section deleted.
+    pub is_deleted: bool,
+
+    /// Whether the section is marked as archived.
+    pub is_archived: bool,
 }
 
 // Automatically generate:
@@ -350,9 +509,8 @@ pub struct ProjectStruct {
 // - 'Debug ' impl for ... debugging.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SyncStruct {
-    /// Collaborators
-    /// Note: Not yet used, so we don't parse it yet.
-    pub collaborators: Option<Vec<NotYetUsedStruct>>,
+    /// The users sharing projects with the user.
+    pub collaborators: Option<Vec<CollaboratorStruct>>,
 
     /// Collaborator states.
     /// Note: Not yet used, so we don't parse it yet.
@@ -378,11 +536,13 @@ pub struct SyncStruct {
 
     /// The last live notification the user saw.
     /// Used for implementing unread notifications.
-    pub live_notifications_last_read_id: Option<u64>,
+    pub live_notifications_last_read_id: Option<String>,
 
-    /// An array of notes.
-    /// Note: Not yet used, so we don't parse it yet.
-    pub notes: Option<Vec<NotYetUsedStruct>>,
+    /// An array of item notes.
+    pub notes: Option<Vec<NoteStruct>>,
+
+    /// An array of project notes.
+    pub project_notes: Option<Vec<NoteStruct>>,
 
     /// An array of projects.
     pub projects: Option<Vec<ProjectStruct>>,
@@ -390,6 +550,9 @@ pub struct SyncStruct {
     /// An array of reminders.
     pub reminders: Option<Vec<ReminderStruct>>,
 
+    /// An array of sections.
+    pub sections: Option<Vec<SectionStruct>>,
+
     /// A new synchronization token.
     /// Used by the client on the next sync request to do incremental sync.
     pub sync_token: String,
@@ -397,132 +560,317 @@ pub struct SyncStruct {
     /// The users information.
     pub user: Option<UserStruct>,
 
-    /// No idea what this is used for.
-    /// Note: Not yet used, so we don't parse it yet.
-    pub temp_id_mapping: Option<NotYetUsedStruct>,
+    /// Maps the temporary ids of objects created by commands
+    /// to the real ids the server assigned to them.
+    pub temp_id_mapping: Option<HashMap<String, String>>,
+
+    /// The result of each command sent, keyed by the command uuid.
+    pub sync_status: Option<HashMap<String, CommandStatus>>,
 }
 
-#[cfg(test)]
-use serde_json;
+/// A command which modifies resources on the server.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CommandStruct {
+    /// The type of the command, for example `item_add`.
+    #[serde(rename = "type")]
+    pub command_type: String,
+
+    /// A unique identifier, the server uses it to only run the command once.
+    pub uuid: String,
+
+    /// A temporary id for the object created by the command,
+    /// it can be used by later commands in the same batch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_id: Option<String>,
+
+    /// The arguments of the command.
+    pub args: serde_json::Value,
+}
+
+/// The result of a single command.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum CommandStatus {
+    /// The command succeeded, the server reports this as `"ok"`.
+    Ok(String),
+
+    /// The command failed.
+    Error {
+        /// The error code of the failure.
+        error_code: i64,
+
+        /// A description of the failure.
+        error: String,
+    },
+}
 
 #[test]
 fn item_deserialize_test() {
     let json_item = r#"{
-                      "all_day": true,
-                      "id": 33511505,
-                      "user_id": 1855589,
-                      "project_id": 128501470,
-                      "content": "Task1",
-                      "date_string": "",
-                      "date_lang": "en",
-                      "due_date_utc": null,
-                      "indent": 1,
-                      "priority": 1,
-                      "item_order": 1,
-                      "day_order": -1,
-                      "collapsed": 0,
-                      "labels": [12839231, 18391839],
-                      "assigned_by_uid": 1855589,
-                      "responsible_uid": null,
-                      "checked": 0,
-                      "in_history": 0,
-                      "is_deleted": 0,
-                      "is_archived": 0,
-                      "sync_id": null,
-                      "date_added": "Fri 26 Sep 2014 08:25:05 +0000"
-                    }"#;
-
-    let item: ItemStruct = serde_json::from_str(&json_item).unwrap();
-
-    assert_eq!(item.id, 33_511_505);
-    assert_eq!(item.user_id, 1_855_589);
-    assert_eq!(item.content, "Task1");
-    assert_eq!(item.date_string, "");
-    assert_eq!(item.date_lang, "en");
-    assert_eq!(item.due_date_utc, None);
-    assert_eq!(item.indent, 1);
+      "id": "2995104339",
+      "user_id": "2671355",
+      "project_id": "2203306141",
+      "content": "Buy Milk",
+      "description": "",
+      "priority": 1,
+      "due": {
+          "date": "2016-12-01",
+          "timezone": null,
+          "string": "every day",
+          "lang": "en",
+          "is_recurring": true
+      },
+      "parent_id": null,
+      "child_order": 1,
+      "section_id": null,
+      "day_order": -1,
+      "collapsed": false,
+      "labels": ["Food", "Shopping"],
+      "added_by_uid": "2671355",
+      "assigned_by_uid": "2671355",
+      "responsible_uid": null,
+      "checked": false,
+      "is_deleted": false,
+      "sync_id": null,
+      "added_at": "2014-09-26T08:25:05.000000Z",
+      "completed_at": null,
+      "duration": null
+    }"#;
+
+    let item: ItemStruct = serde_json::from_str(json_item).unwrap();
+
+    assert_eq!(item.id, "2995104339");
+    assert_eq!(item.user_id, "2671355");
+    assert_eq!(item.project_id, "2203306141");
+    assert_eq!(item.content, "Buy Milk");
     assert_eq!(item.priority, 1);
-    assert_eq!(item.item_order, 1);
+    assert_eq!(item.parent_id, None);
+    assert_eq!(item.child_order, 1);
+    assert_eq!(item.section_id, None);
     assert_eq!(item.day_order, -1);
-    assert_eq!(item.collapsed, 0);
-    assert_eq!(item.labels, vec![12_839_231, 18_391_839]);
-    assert_eq!(item.assigned_by_uid.unwrap(), 1_855_589);
+    assert!(!item.collapsed);
+    assert_eq!(item.labels, vec!["Food", "Shopping"]);
+    assert_eq!(item.assigned_by_uid.unwrap(), "2671355");
     assert_eq!(item.responsible_uid, None);
-    assert_eq!(item.checked, 0);
-    assert_eq!(item.in_history, 0);
-    assert_eq!(item.is_deleted, 0);
-    assert_eq!(item.is_archived, 0);
-    assert_eq!(item.sync_id, None);
-    assert_eq!(item.date_added.unwrap(), "Fri 26 Sep 2014 08:25:05 +0000");
+    assert!(!item.checked);
+    assert!(!item.is_deleted);
+    assert_eq!(item.added_at.unwrap(), "2014-09-26T08:25:05.000000Z");
+
+    let due = item.due.unwrap();
+    assert_eq!(due.date, "2016-12-01");
+    assert_eq!(due.string, "every day");
+    assert!(due.is_recurring);
 }
 
 #[test]
-fn item_deserialize_latest_test() {
-    let json_item = r#"{
-      "all_day": true,
-      "assigned_by_uid": 7261,
-      "checked": 0,
-      "collapsed": 0,
-      "content": "Order bike electric regulator.",
-      "date_added": "Sat 08 Jun 2019 13:00:28 +0000",
-      "date_completed": null,
-      "date_lang": "en",
-      "date_string": "22 Jun",
-      "day_order": 10,
-      "due_date_utc": "Sun 23 Jun 2019 06:59:59 +0000",
-      "id": 3238150997,
-      "in_history": 0,
-      "indent": 1,
-      "is_archived": 0,
-      "is_deleted": 0,
-      "item_order": 5,
-      "labels": [],
+fn note_deserialize_test() {
+    let json_note = r#"{
+      "id": "2992679862",
+      "posted_uid": "2671355",
+      "item_id": "2995104339",
+      "content": "Note",
+      "file_attachment": {
+        "file_type": "text/plain",
+        "file_name": "File1.txt",
+        "file_size": 1234,
+        "file_url": "https://example.com/File1.txt",
+        "upload_state": "completed"
+      },
+      "uids_to_notify": null,
+      "is_deleted": false,
+      "posted_at": "2014-10-01T14:54:55.000000Z",
+      "reactions": { "❤️": ["2671362"] }
+    }"#;
+
+    let note: NoteStruct = serde_json::from_str(json_note).unwrap();
+
+    assert_eq!(note.item_id.as_deref(), Some("2995104339"));
+    assert_eq!(note.project_id, None);
+    assert_eq!(note.content, "Note");
+
+    let attachment = note.file_attachment.unwrap();
+    assert_eq!(attachment.file_name.as_deref(), Some("File1.txt"));
+    assert_eq!(attachment.resource_type, None);
+}
+
+#[test]
+fn reminder_deserialize_test() {
+    let json_reminder = r#"{
+      "id": "2992683215",
+      "notify_uid": "2671355",
+      "item_id": "2995104339",
+      "type": "absolute",
+      "due": {
+        "date": "2016-08-05T07:00:00Z",
+        "timezone": null,
+        "is_recurring": false,
+        "string": "tomorrow at 10:00",
+        "lang": "en"
+      },
+      "minute_offset": 180,
+      "is_deleted": false
+    }"#;
+
+    let reminder: ReminderStruct = serde_json::from_str(json_reminder).unwrap();
+
+    assert_eq!(reminder.item_id, "2995104339");
+    assert_eq!(reminder.reminder_type, ReminderType::Absolute);
+    assert_eq!(reminder.due.unwrap().date, "2016-08-05T07:00:00Z");
+    assert_eq!(reminder.minute_offset, Some(180));
+
+    let json_reminder = r#"{
+      "id": "1", "notify_uid": null, "item_id": "2", "type": "geofence", "is_deleted": false
+    }"#;
+    let reminder: ReminderStruct = serde_json::from_str(json_reminder).unwrap();
+    assert_eq!(reminder.reminder_type, ReminderType::Unknown);
+}
+
+#[test]
+fn label_deserialize_test() {
+    let json_label = r#"{
+      "id": "2156154810",
+      "name": "Food",
+      "color": "lime_green",
+      "item_order": 0,
+      "is_deleted": false,
+      "is_favorite": false
+    }"#;
+
+    let label: LabelStruct = serde_json::from_str(json_label).unwrap();
+
+    assert_eq!(label.id, "2156154810");
+    assert_eq!(label.name, "Food");
+    assert_eq!(label.color, "lime_green");
+    assert_eq!(label.item_order, 0);
+    assert!(!label.is_favorite);
+}
+
+#[test]
+fn project_deserialize_test() {
+    let json_project = r#"{
+      "id": "2203306141",
+      "name": "Shopping List",
+      "color": "lime_green",
       "parent_id": null,
-      "priority": 1,
-      "project_id": 2211785308,
-      "responsible_uid": null,
+      "child_order": 1,
+      "collapsed": false,
+      "shared": false,
+      "can_assign_tasks": false,
+      "is_deleted": false,
+      "is_archived": false,
+      "is_favorite": false,
       "sync_id": null,
-      "user_id": 7261
+      "inbox_project": true,
+      "view_style": "list"
     }"#;
 
-    let _item: ItemStruct = serde_json::from_str(&json_item).unwrap();
+    let project: ProjectStruct = serde_json::from_str(json_project).unwrap();
+
+    assert_eq!(project.id, "2203306141");
+    assert_eq!(project.name, "Shopping List");
+    assert_eq!(project.color, "lime_green");
+    assert!(project.inbox_project);
 }
 
 #[test]
 fn sync_deserialize_test() {
     let json_item = r#"{
       "full_sync": true,
-      "items": [
+      "items": [],
+      "sections": [
           {
-          "all_day": true,
-          "assigned_by_uid": 7261,
-          "checked": 0,
-          "collapsed": 0,
-          "content": "Order bike electric regulator.",
-          "date_added": "Sat 08 Jun 2019 13:00:28 +0000",
-          "date_completed": null,
-          "date_lang": "en",
-          "date_string": "22 Jun",
-          "day_order": 10,
-          "due_date_utc": "Sun 23 Jun 2019 06:59:59 +0000",
-          "id": 3238150997,
-          "in_history": 0,
-          "indent": 1,
-          "is_archived": 0,
-          "is_deleted": 0,
-          "item_order": 5,
-          "labels": [],
-          "parent_id": null,
-          "priority": 1,
-          "project_id": 2211785308,
-          "responsible_uid": null,
+          "id": "7025",
+          "name": "Groceries",
+          "project_id": "2203306141",
+          "section_order": 1,
+          "collapsed": false,
+          "user_id": "2671355",
           "sync_id": null,
-          "user_id": 7261
-         }
+          "is_deleted": false,
+          "is_archived": false,
+          "archived_at": null,
+          "added_at": "2019-10-07T07:09:27.000000Z"
+          }
       ],
-      "sync_token": "PPHBRv43cSLUtndVYZxjN4JENgQ2AALKeESwpEhysYEtGxJBshJTCGVfPbRJLUGaMAqbcEYtrHB05wdO-6p2nHflyDYPzzjrwlf29nEDsfOVn1s",
+      "sync_token": "TnYUZEpuzf2FMA9qzyY3j4xky6dXiYejmSO85S5paZ_a9y1FI85mBbIWZGpW",
       "temp_id_mapping": {}
     }"#;
 
-    let _item: SyncStruct = serde_json::from_str(&json_item).unwrap();
+    let sync: SyncStruct = serde_json::from_str(json_item).unwrap();
+    assert_eq!(sync.sections.unwrap()[0].name, "Groceries");
+}
+
+#[test]
+fn task_deserialize_test() {
+    // Fields of the REST API which we don't use are ignored.
+    let json_tasks = r#"[
+      {
+        "id": "2995104339",
+        "project_id": "2203306141",
+        "content": "Buy Milk",
+        "priority": 4,
+        "labels": ["Food"],
+        "url": "https://todoist.com/showTask?id=2995104339"
+      }
+    ]"#;
+
+    let tasks: Vec<TaskStruct> = serde_json::from_str(json_tasks).unwrap();
+    assert_eq!(tasks[0].id, "2995104339");
+}
+
+#[test]
+fn completed_deserialize_test() {
+    // Without `annotate_items` only a summary of the item is sent.
+    let json_completed = r#"{
+      "items": [
+        {
+          "content": "Buy Milk",
+          "meta_data": null,
+          "user_id": "2671355",
+          "task_id": "2995104339",
+          "note_count": 0,
+          "project_id": "2203306141",
+          "section_id": "7025",
+          "completed_at": "2015-02-17T15:40:41.000000Z",
+          "id": "1899066186"
+        }
+      ],
+      "projects": {},
+      "sections": {}
+    }"#;
+
+    let completed: CompletedStruct = serde_json::from_str(json_completed).unwrap();
+    assert_eq!(completed.items[0].task_id, "2995104339");
+    assert!(completed.items[0].item_object.is_none());
+}
+
+#[test]
+fn command_response_deserialize_test() {
+    let json_item = r#"{
+      "full_sync": false,
+      "sync_status": {
+          "2f9a8c0e-5e0e-4a25-9c47-8b3c3c1e6f9b": "ok",
+          "b2ab2ba4-3b9a-4e1f-a1b5-d7f3e0c0a1e2": { "error_code": 15, "error": "Invalid temporary id" }
+      },
+      "sync_token": "VRyFHr0Qo3Hr--pzINyT6nax4vW7X2YG5RQlw3lB-6eYOPbSZVJepa62EVhO",
+      "temp_id_mapping": { "43f7ed23-a038-46b5-b2c9-4abda9097ffa": "2995104340" }
+    }"#;
+
+    let sync: SyncStruct = serde_json::from_str(json_item).unwrap();
+
+    let mapping = sync.temp_id_mapping.unwrap();
+    assert_eq!(
+        mapping["43f7ed23-a038-46b5-b2c9-4abda9097ffa"],
+        "2995104340"
+    );
+
+    let status = sync.sync_status.unwrap();
+    match status["2f9a8c0e-5e0e-4a25-9c47-8b3c3c1e6f9b"] {
+        CommandStatus::Ok(ref ok) => assert_eq!(ok, "ok"),
+        CommandStatus::Error { .. } => panic!("Expected command to succeed"),
+    }
+    match status["b2ab2ba4-3b9a-4e1f-a1b5-d7f3e0c0a1e2"] {
+        CommandStatus::Ok(_) => panic!("Expected command to fail"),
+        CommandStatus::Error { error_code, .. } => assert_eq!(error_code, 15),
+    }
 }